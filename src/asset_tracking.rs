@@ -14,6 +14,14 @@ pub trait LoadResource {
     /// have been loaded, it will be inserted as a resource. This ensures that the resource only
     /// exists when the assets are ready.
     fn load_resource<T: Resource + Asset + Clone + FromWorld>(&mut self) -> &mut Self;
+
+    /// Like [`LoadResource::load_resource`], but the [`Asset`] is read from `path` by its
+    /// registered [`AssetLoader`] instead of being built with [`FromWorld`].
+    /// The loader has to be registered before calling this.
+    fn load_resource_from_path<T: Resource + Asset + Clone>(
+        &mut self,
+        path: &'static str,
+    ) -> &mut Self;
 }
 
 impl LoadResource for App {
@@ -23,17 +31,33 @@ impl LoadResource for App {
         let value = T::from_world(world);
         let assets = world.resource::<AssetServer>();
         let handle = assets.add(value);
-        let mut handles = world.resource_mut::<ResourceHandles>();
-        handles
-            .waiting
-            .push_back((handle.untyped(), |world, handle| {
-                let assets = world.resource::<Assets<T>>();
-                if let Some(value) = assets.get(handle.id().typed::<T>()) {
-                    world.insert_resource(value.clone());
-                }
-            }));
+        wait_for_resource(world, handle);
         self
     }
+
+    fn load_resource_from_path<T: Resource + Asset + Clone>(
+        &mut self,
+        path: &'static str,
+    ) -> &mut Self {
+        self.init_asset::<T>();
+        let world = self.world_mut();
+        let assets = world.resource::<AssetServer>();
+        let handle: Handle<T> = assets.load(path);
+        wait_for_resource(world, handle);
+        self
+    }
+}
+
+fn wait_for_resource<T: Resource + Asset + Clone>(world: &mut World, handle: Handle<T>) {
+    let mut handles = world.resource_mut::<ResourceHandles>();
+    handles
+        .waiting
+        .push_back((handle.untyped(), |world, handle| {
+            let assets = world.resource::<Assets<T>>();
+            if let Some(value) = assets.get(handle.id().typed::<T>()) {
+                world.insert_resource(value.clone());
+            }
+        }));
 }
 
 /// A function that inserts a loaded resource.
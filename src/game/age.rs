@@ -14,6 +14,7 @@ use crate::{
 };
 
 pub(super) fn plugin(app: &mut App) {
+    app.add_event::<AgeChanged>();
     app.add_systems(PreUpdate, update_turnback);
     app.add_systems(
         Update,
@@ -30,7 +31,7 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_systems(
         Update,
-        (tick_timer_record, record_death, die)
+        (tick_timer_record, record_death, die, track_age)
             .in_set(AgedSystems)
             .run_if(in_state(Screen::Gameplay)),
     );
@@ -57,16 +58,25 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Age {
     Young,
     Old,
     Ancient,
 }
 
+/// Sent when the player's [`Age`] changes.
+#[derive(Event)]
+pub struct AgeChanged {
+    pub from: Age,
+    pub to: Age,
+}
+
 #[derive(Component)]
 pub struct Aged {
     pub time: f64,
+    /// Multiplier for how fast rewinding ages the player.
+    pub rewind_cost: f64,
     turnback: bool,
     record: Timer,
 }
@@ -91,6 +101,7 @@ impl Default for Aged {
     fn default() -> Self {
         Self {
             time: 100.0,
+            rewind_cost: 1.0,
             turnback: false,
             record: Timer::from_seconds(0.2, TimerMode::Once),
         }
@@ -213,7 +224,7 @@ fn time_reverse(
     let Ok(mut aged) = aged_query.single_mut() else {
         return;
     };
-    aged.time -= time.delta_secs_f64() * 10.0;
+    aged.time -= time.delta_secs_f64() * 10.0 * aged.rewind_cost;
     if aged.time <= 0.0 {
         aged.time = 0.0;
         aged.turnback = false;
@@ -351,6 +362,23 @@ fn record_death(mut query: Query<(&mut Timed), Added<Dead>>) {
     }
 }
 
+fn track_age(
+    aged_query: Query<&Aged>,
+    mut last: Local<Option<Age>>,
+    mut changed: EventWriter<AgeChanged>,
+) {
+    let Ok(aged) = aged_query.single() else {
+        return;
+    };
+    let age = aged.to_age();
+    if let Some(from) = *last {
+        if from != age {
+            changed.write(AgeChanged { from, to: age });
+        }
+    }
+    *last = Some(age);
+}
+
 fn tick_timer_record(mut aged_query: Query<&mut Aged>, time: Res<Time>) {
     let Ok(mut aged) = aged_query.single_mut() else {
         return;
@@ -87,6 +87,12 @@ impl AnimationConfig {
         self.playing
     }
 
+    /// Whether `sprite` already shows this animation, a new animation is only applied on the
+    /// next frame tick.
+    pub(crate) fn shows(&self, sprite: &Sprite) -> bool {
+        self.sprite == sprite.image
+    }
+
     pub(crate) fn set_looping(&mut self, looping: bool) {
         self.looping = looping;
     }
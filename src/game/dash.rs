@@ -0,0 +1,160 @@
+//! What happens while the player dashes. Starting a dash grants a few frames of invulnerability
+//! depending on the [`Age`] of the player, dashes can pass through enemies by dropping the enemy
+//! layer from the player's collision filters, and items with [`ItemEffect::DashStrike`] hurt
+//! every enemy dashed through.
+
+use avian2d::prelude::{Collider, CollisionLayers, SpatialQuery, SpatialQueryFilter};
+use bevy::prelude::*;
+use bevy_tnua::prelude::{TnuaAction, TnuaBuiltinDash, TnuaController};
+
+use crate::{
+    AgedSystems, AppSystems,
+    game::{
+        age::{Age, Aged},
+        effects::{ItemEffect, owned_effects},
+        health::{DamageEvent, DamageKind, Health},
+        items::ItemDefs,
+        layers::GameLayer,
+        player::{Player, SpellCap},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (update_dash, dash_strike)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(AgedSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// How dashing protects the player.
+#[derive(Component, Clone)]
+pub struct DashSettings {
+    /// Seconds of invulnerability a dash grants at each age.
+    pub young: f32,
+    pub old: f32,
+    pub ancient: f32,
+    /// Whether dashes pass through enemies.
+    pub phase: bool,
+}
+
+impl Default for DashSettings {
+    fn default() -> Self {
+        Self {
+            young: 0.3,
+            // The old shuffle can be repeated every 0.1 seconds, so it grants nothing.
+            old: 0.0,
+            ancient: 0.2,
+            phase: true,
+        }
+    }
+}
+
+impl DashSettings {
+    pub fn invulnerability(&self, age: Age) -> f32 {
+        match age {
+            Age::Young => self.young,
+            Age::Old => self.old,
+            Age::Ancient => self.ancient,
+        }
+    }
+}
+
+/// The dash in progress.
+#[derive(Component, Default)]
+pub struct Dashing {
+    active: bool,
+    /// Enemies already hurt by this dash.
+    hits: Vec<Entity>,
+}
+
+fn update_dash(
+    player: Single<
+        (
+            &TnuaController,
+            &DashSettings,
+            &Aged,
+            &GlobalTransform,
+            &Collider,
+            &mut Dashing,
+            &mut Health,
+            &mut CollisionLayers,
+        ),
+        With<Player>,
+    >,
+    spatial_query: SpatialQuery,
+) {
+    let (controller, settings, aged, transform, collider, mut dashing, mut health, mut layers) =
+        player.into_inner();
+    let active = controller.action_name() == Some(TnuaBuiltinDash::NAME);
+    if active != dashing.active {
+        dashing.active = active;
+        if active {
+            dashing.hits.clear();
+            health.make_invulnerable(settings.invulnerability(aged.to_age()));
+        }
+    }
+    // Derived from the current dash every frame, so nothing that skips the end of a dash (like a
+    // rewind) can leave the player passing through enemies.
+    if active && settings.phase {
+        layers.filters.remove(GameLayer::Enemy);
+    } else if !layers.filters.has_all(GameLayer::Enemy) {
+        // Colliding with an enemy the player is still inside of would fling them apart.
+        let inside = spatial_query.shape_intersections(
+            collider,
+            transform.translation().xy(),
+            0.0,
+            &SpatialQueryFilter::from_mask(GameLayer::Enemy),
+        );
+        if inside.is_empty() {
+            layers.filters.add(GameLayer::Enemy);
+        }
+    }
+}
+
+fn dash_strike(
+    player: Single<(Entity, &GlobalTransform, &Collider, &SpellCap, &mut Dashing), With<Player>>,
+    spatial_query: SpatialQuery,
+    mut damage: EventWriter<DamageEvent>,
+    defs: Res<ItemDefs>,
+) {
+    let (entity, transform, collider, spells, mut dashing) = player.into_inner();
+    if !dashing.active {
+        return;
+    }
+    let strike: f32 = owned_effects(spells, &defs)
+        .filter_map(|effect| match effect {
+            ItemEffect::DashStrike { damage } => Some(*damage),
+            _ => None,
+        })
+        .sum();
+    if strike <= 0.0 {
+        return;
+    }
+    let hits = spatial_query.shape_intersections(
+        collider,
+        transform.translation().xy(),
+        0.0,
+        &SpatialQueryFilter::from_mask(GameLayer::Enemy),
+    );
+    for hit in hits {
+        if dashing.hits.contains(&hit) {
+            continue;
+        }
+        dashing.hits.push(hit);
+        damage.write(DamageEvent {
+            target: hit,
+            source: Some(entity),
+            amount: strike,
+            kind: DamageKind::Physical,
+            knockback: Vec2::Y * 150.0,
+            stun: 0.3,
+            inflicts_status: true,
+            lethal: false,
+        });
+    }
+}
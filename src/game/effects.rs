@@ -0,0 +1,234 @@
+//! Item effects and the hooks they are triggered from.
+//!
+//! Every owned item contributes its [`ItemEffect`]s. The systems in here listen for gameplay
+//! events (casting, hitting, killing, taking damage, rewinding, ageing) and let each effect
+//! react to them, so new items only need a new variant and a match arm in the relevant hook.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    AgedSystems, AppSystems, Turnback,
+    game::{
+        age::{AgeChanged, Aged, Dead},
+        health::{DamageTaken, Died, Health},
+        items::{ItemDefs, ItemId},
+        player::{Player, SpellCap},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<ItemAcquired>();
+    app.add_systems(
+        Update,
+        (
+            on_acquire,
+            on_hit,
+            on_kill,
+            on_damage_taken,
+            on_age_change,
+            regenerate,
+        )
+            .in_set(AppSystems::Update)
+            .in_set(AgedSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        OnEnter(Turnback(true)),
+        on_rewind.run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, Reflect)]
+pub enum ItemEffect {
+    /// Cast `extra` additional projectiles fanned out by `spread` degrees.
+    MultiShot { extra: u32, spread: f32 },
+    /// Projectiles pass through `count` enemies before breaking.
+    Pierce { count: u32 },
+    /// Heal for a fraction of all damage the player deals.
+    Lifesteal { fraction: f32 },
+    /// Survive one killing blow.
+    ExtraLife,
+    /// Multiplies how fast rewinding ages the player.
+    RewindCost { factor: f64 },
+    /// Heal whenever an enemy dies.
+    KillHeal { amount: f32 },
+    /// Turn back the clock a little whenever the player gets hurt.
+    DamageYouth { amount: f64 },
+    /// Heal whenever the player's age changes.
+    AgeHeal { amount: f32 },
+    /// Raises the maximum health of the player.
+    MaxHealth { amount: f32 },
+    /// Heal `per_second` every second.
+    Regen { per_second: f32 },
+    /// Dashing through an enemy deals `damage` to it.
+    DashStrike { damage: f32 },
+}
+
+/// A spell about to be cast, modified by the on cast hook before the projectiles are spawned.
+pub struct Cast {
+    pub dmg: f32,
+    pub shots: u32,
+    pub spread: f32,
+    pub pierce: u32,
+}
+
+impl Cast {
+    pub fn new(dmg: f32) -> Self {
+        Self {
+            dmg,
+            shots: 1,
+            spread: 0.0,
+            pierce: 0,
+        }
+    }
+
+    /// Directions of every projectile of this cast, fanned out around `dir`.
+    pub fn directions(&self, dir: Vec2) -> impl Iterator<Item = Vec2> {
+        let spread = self.spread.to_radians();
+        let center = (self.shots as f32 - 1.0) / 2.0;
+        (0..self.shots).map(move |i| Vec2::from_angle((i as f32 - center) * spread).rotate(dir))
+    }
+}
+
+impl ItemEffect {
+    pub fn on_cast(&self, cast: &mut Cast) {
+        match *self {
+            ItemEffect::MultiShot { extra, spread } => {
+                cast.shots += extra;
+                cast.spread = cast.spread.max(spread);
+            }
+            ItemEffect::Pierce { count } => cast.pierce += count,
+            _ => (),
+        }
+    }
+}
+
+/// Sent when the player picks up an item.
+#[derive(Event)]
+pub struct ItemAcquired(pub ItemId);
+
+/// All effects of the items the player currently owns.
+pub fn owned_effects<'a>(
+    spells: &'a SpellCap,
+    defs: &'a ItemDefs,
+) -> impl Iterator<Item = &'a ItemEffect> {
+    spells
+        .items
+        .iter()
+        .flat_map(move |item| defs.get(*item).effects.iter())
+}
+
+fn on_acquire(
+    mut events: EventReader<ItemAcquired>,
+    mut player: Single<&mut Health, With<Player>>,
+    defs: Res<ItemDefs>,
+) {
+    for ItemAcquired(item) in events.read() {
+        for effect in &defs.get(*item).effects {
+            match effect {
+                ItemEffect::ExtraLife => player.lives += 1,
+                ItemEffect::MaxHealth { amount } => player.raise_max_health(*amount),
+                _ => (),
+            }
+        }
+    }
+}
+
+fn on_hit(
+    mut events: EventReader<DamageTaken>,
+    player: Single<(Entity, &SpellCap, &mut Health), With<Player>>,
+    defs: Res<ItemDefs>,
+) {
+    let (entity, spells, mut health) = player.into_inner();
+    for hit in events
+        .read()
+        .filter(|hit| hit.source == Some(entity) && hit.entity != entity)
+    {
+        for effect in owned_effects(spells, &defs) {
+            if let ItemEffect::Lifesteal { fraction } = effect {
+                health.heal(hit.amount * fraction);
+            }
+        }
+    }
+}
+
+fn on_kill(
+    mut events: EventReader<Died>,
+    player: Single<(Entity, &SpellCap, &mut Health), With<Player>>,
+    defs: Res<ItemDefs>,
+) {
+    let (entity, spells, mut health) = player.into_inner();
+    for _ in events.read().filter(|died| died.killer == Some(entity)) {
+        for effect in owned_effects(spells, &defs) {
+            if let ItemEffect::KillHeal { amount } = effect {
+                health.heal(*amount);
+            }
+        }
+    }
+}
+
+fn on_damage_taken(
+    mut events: EventReader<DamageTaken>,
+    player: Single<(Entity, &SpellCap, &mut Aged), With<Player>>,
+    defs: Res<ItemDefs>,
+) {
+    let (entity, spells, mut aged) = player.into_inner();
+    for _ in events.read().filter(|damage| damage.entity == entity) {
+        for effect in owned_effects(spells, &defs) {
+            if let ItemEffect::DamageYouth { amount } = effect {
+                aged.time = (aged.time + amount).min(100.0);
+            }
+        }
+    }
+}
+
+fn on_rewind(player: Single<(&SpellCap, &mut Aged), With<Player>>, defs: Res<ItemDefs>) {
+    let (spells, mut aged) = player.into_inner();
+    aged.rewind_cost = owned_effects(spells, &defs)
+        .filter_map(|effect| match effect {
+            ItemEffect::RewindCost { factor } => Some(*factor),
+            _ => None,
+        })
+        .product();
+}
+
+fn on_age_change(
+    mut events: EventReader<AgeChanged>,
+    player: Single<(&SpellCap, &mut Health), With<Player>>,
+    defs: Res<ItemDefs>,
+) {
+    let (spells, mut health) = player.into_inner();
+    for _ in events.read() {
+        for effect in owned_effects(spells, &defs) {
+            if let ItemEffect::AgeHeal { amount } = effect {
+                health.heal(*amount);
+            }
+        }
+    }
+}
+
+fn regenerate(
+    player: Single<(&SpellCap, &mut Health), (With<Player>, Without<Dead>)>,
+    defs: Res<ItemDefs>,
+    time: Res<Time>,
+    mut tick: Local<Timer>,
+) {
+    // Heal in whole ticks once a second so the heal feedback does not flash every frame.
+    if tick.duration().is_zero() {
+        *tick = Timer::from_seconds(1.0, TimerMode::Repeating);
+    }
+    tick.tick(time.delta());
+    if !tick.just_finished() {
+        return;
+    }
+    let (spells, mut health) = player.into_inner();
+    let regen: f32 = owned_effects(spells, &defs)
+        .filter_map(|effect| match effect {
+            ItemEffect::Regen { per_second } => Some(*per_second),
+            _ => None,
+        })
+        .sum();
+    health.heal(regen);
+}
@@ -0,0 +1,706 @@
+//! Shared enemy AI. Enemies are composed from a [`Brain`], a [`Perception`], a [`Locomotion`] and
+//! any number of behaviours ([`Patrol`], [`Chase`], [`KeepDistance`], [`Melee`], [`Ranged`]) which
+//! each act in one of the [`AiState`]s. Walkers with a [`Navigator`] chase the player across
+//! platforms using the [`NavGraph`] of the level, flyers are moved by [`steering`]. Enemies of
+//! the same room alert each other and take turns attacking through their [`Squad`].
+//!
+//! Melee attacks follow the frames of the attack animation: a telegraphed windup, the active
+//! frames in which the attack can hit once, and a recovery in which the enemy is open.
+
+use avian2d::prelude::{Collider, SpatialQuery, SpatialQueryFilter};
+use bevy::prelude::*;
+use bevy_tnua::{
+    math::Float,
+    prelude::{TnuaBuiltinDash, TnuaBuiltinJump, TnuaBuiltinWalk, TnuaController},
+};
+use rand::Rng;
+
+use crate::{
+    AgedSystems, AppSystems,
+    game::{
+        age::Dead,
+        animate::{AnimationConfig, Directional},
+        enemies::{
+            squad::{Squad, SquadTactics, alert, broadcast, flank, join_squad, tally},
+            steering::{self, steer},
+        },
+        health::{DamageEvent, DamageKind, Stunned},
+        layers::GameLayer,
+        player::Player,
+        projectile::{Projectile, ProjectileSpawner, ProjectileTarget},
+        world::nav::{LinkKind, NavGraph, NavStep},
+    },
+    screens::Screen,
+};
+
+/// How often per second an enemy winding up an attack flashes.
+const TELEGRAPH_FLASH: f32 = 20.0;
+const TELEGRAPH_TINT: Color = Color::linear_rgb(1.0, 0.6, 0.2);
+const TELEGRAPH_MARKER: Color = Color::linear_rgba(1.0, 0.1, 0.1, 0.45);
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            join_squad,
+            sense,
+            (broadcast, alert, tally).chain(),
+            (patrol, chase, flank, navigate, melee, ranged, keep_distance).chain(),
+            (drive, steer),
+        )
+            .chain()
+            .in_set(AgedSystems)
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    // Runs after the hurt and status tints have been applied so the flash stays visible.
+    app.add_systems(PostUpdate, telegraph.run_if(in_state(Screen::Gameplay)));
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub(super) enum AiState {
+    #[default]
+    Roaming,
+    Aggro,
+    Attacking,
+}
+
+#[derive(Component, Clone, Default)]
+pub(super) struct Brain {
+    pub state: AiState,
+    /// Direction the enemy is heading in.
+    pub facing: Vec2,
+    /// Movement wanted this frame, written by the behaviours and applied by [`drive`].
+    pub movement: Vec2,
+    /// Height of the jump wanted this frame.
+    pub jump: Option<f32>,
+    /// Set whenever the state changes so the enemy can switch its animation.
+    pub dirty: bool,
+}
+
+impl Brain {
+    pub fn facing(facing: Vec2) -> Self {
+        Self {
+            facing,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_state(&mut self, state: AiState) {
+        if self.state != state {
+            self.state = state;
+            self.dirty = true;
+        }
+    }
+}
+
+/// What an enemy knows about the player and its surroundings, updated by [`sense`].
+#[derive(Component, Clone)]
+pub(super) struct Perception {
+    /// How far the enemy can see.
+    pub sight: f32,
+    /// Distance at which the enemy gives up chasing a player it can't see.
+    pub forget: f32,
+    pub sees_player: bool,
+    pub player_pos: Vec2,
+    pub player_distance: f32,
+    /// Where the player was last seen.
+    pub last_seen: Option<Vec2>,
+    /// Whether a walker is blocked by a wall or a ledge ahead.
+    pub obstacle: bool,
+    /// Distance to the ground below, only probed for flyers.
+    pub floor: Option<f32>,
+}
+
+impl Perception {
+    pub fn new(sight: f32, forget: f32) -> Self {
+        Self {
+            sight,
+            forget,
+            sees_player: false,
+            player_pos: Vec2::ZERO,
+            player_distance: f32::INFINITY,
+            last_seen: None,
+            obstacle: false,
+            floor: None,
+        }
+    }
+
+    fn to_player(&self, pos: Vec2) -> Vec2 {
+        (self.player_pos - pos).normalize_or_zero()
+    }
+}
+
+#[derive(Component, Clone)]
+pub(super) enum Locomotion {
+    /// Walks on the ground using a [`TnuaController`], `jump` is the highest ledge it can climb.
+    Walker {
+        speed: f32,
+        float_height: f32,
+        jump: f32,
+    },
+    /// Floats through the air, trying to keep between `hover` units above the ground.
+    Flyer { speed: f32, hover: (f32, f32) },
+}
+
+/// Wander around while the player is out of sight.
+#[derive(Component, Clone, Default)]
+pub(super) struct Patrol {
+    /// Picks a new random direction whenever this runs out, otherwise walks back and forth.
+    pub wander: Option<Timer>,
+}
+
+/// Follow the player once spotted.
+#[derive(Component, Clone, Default)]
+pub(super) struct Chase;
+
+/// Follow a path through the [`NavGraph`] while chasing a player on another platform.
+#[derive(Component, Clone)]
+pub(super) struct Navigator {
+    path: Vec<NavStep>,
+    repath: Timer,
+}
+
+impl Default for Navigator {
+    fn default() -> Self {
+        Self {
+            path: Vec::new(),
+            repath: Timer::from_seconds(0.5, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Back off from the player while attacking.
+#[derive(Component, Clone)]
+pub(super) struct KeepDistance {
+    pub min: f32,
+}
+
+#[derive(Component, Clone)]
+#[require(Swing)]
+pub(super) struct Melee {
+    /// Distance at which the attack starts.
+    pub trigger: f32,
+    /// Radius around the enemy that is hit while attacking.
+    pub reach: f32,
+    /// First and last frame of the attack animation in which the attack hits, the frames before
+    /// are the windup and the frames after the recovery.
+    pub active: (usize, usize),
+    pub damage: f32,
+    pub knockback: f32,
+    pub stun: f32,
+    /// Lunges this far in the direction it wound up in once the attack becomes active.
+    pub dash: Option<f32>,
+    pub cooldown: Timer,
+}
+
+impl Melee {
+    fn phase(&self, frame: usize) -> AttackPhase {
+        match frame {
+            frame if frame < self.active.0 => AttackPhase::Windup,
+            frame if frame <= self.active.1 => AttackPhase::Active,
+            _ => AttackPhase::Recovery,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum AttackPhase {
+    Windup,
+    Active,
+    Recovery,
+}
+
+/// The melee attack in progress.
+#[derive(Component, Clone, Default)]
+pub(super) struct Swing {
+    pub phase: Option<AttackPhase>,
+    /// Whether the attack already hit, every attack hits at most once.
+    hit: bool,
+    /// The ground marker shown during the windup.
+    marker: Option<Entity>,
+}
+
+#[derive(Component, Clone)]
+pub(super) struct Ranged {
+    /// Distance at which the enemy starts shooting.
+    pub range: f32,
+    /// Distance at which the enemy stops shooting and chases again.
+    pub leave: f32,
+    pub damage: f32,
+    pub kind: DamageKind,
+    pub speed: f32,
+    pub knockback: f32,
+    pub stun: f32,
+    /// Animation frame on which the projectile leaves.
+    pub fire_frame: usize,
+    pub cooldown: Timer,
+    pub aim: Vec2,
+    pub sprite: Handle<Image>,
+    pub atlas: Handle<TextureAtlasLayout>,
+    pub frames: (usize, usize),
+}
+
+fn sense(
+    mut query: Query<
+        (
+            &GlobalTransform,
+            &mut Brain,
+            &mut Perception,
+            &Locomotion,
+            Entity,
+        ),
+        Without<Dead>,
+    >,
+    player: Single<(&GlobalTransform, Entity), With<Player>>,
+    spatial_query: SpatialQuery,
+) {
+    let (player, playerentity) = player.into_inner();
+    let playerpos = player.translation().xy();
+    for (transform, mut brain, mut perception, locomotion, entity) in query.iter_mut() {
+        let pos = transform.translation().xy();
+        brain.movement = Vec2::ZERO;
+        brain.jump = None;
+        perception.player_pos = playerpos;
+        perception.player_distance = pos.distance(playerpos);
+
+        let filter =
+            SpatialQueryFilter::from_mask(GameLayer::solid()).with_excluded_entities([entity]);
+        perception.sees_player = perception.player_distance <= perception.sight
+            && Dir2::new(playerpos - pos).is_ok_and(|ndir| {
+                spatial_query
+                    .cast_ray(pos, ndir, perception.sight, true, &filter)
+                    .is_some_and(|rayhit| rayhit.entity == playerentity)
+            });
+        if perception.sees_player {
+            perception.last_seen = Some(playerpos);
+        }
+
+        let filter = SpatialQueryFilter::from_mask(GameLayer::solid())
+            .with_excluded_entities([entity, playerentity]);
+        match locomotion {
+            Locomotion::Walker { .. } => {
+                let ahead = Vec2::new(brain.facing.x.signum(), 0.0);
+                let rayhit =
+                    spatial_query.cast_ray(pos + ahead * 25.0, Dir2::NEG_Y, 50.0, true, &filter);
+                perception.obstacle =
+                    rayhit.is_none_or(|rayhit| rayhit.distance <= 10.0 || rayhit.distance >= 35.0);
+                perception.floor = rayhit.map(|rayhit| rayhit.distance);
+            }
+            Locomotion::Flyer { .. } => {
+                perception.floor = spatial_query
+                    .cast_ray(pos, Dir2::NEG_Y, 1000.0, true, &filter)
+                    .map(|rayhit| rayhit.distance);
+            }
+        }
+    }
+}
+
+fn patrol(
+    mut query: Query<
+        (&mut Brain, &mut Patrol, &Perception, &Locomotion),
+        (Without<Dead>, Without<Stunned>),
+    >,
+    time: Res<Time>,
+) {
+    for (mut brain, mut patrol, perception, locomotion) in query.iter_mut() {
+        if brain.state != AiState::Roaming {
+            continue;
+        }
+        match locomotion {
+            Locomotion::Walker { .. } if perception.obstacle => brain.facing = -brain.facing,
+            Locomotion::Walker { .. } => (),
+            Locomotion::Flyer { .. } => {
+                brain.facing = steering::wander(brain.facing, 2.0 * time.delta_secs());
+            }
+        }
+        if let Some(wander) = &mut patrol.wander {
+            wander.tick(time.delta());
+            if wander.finished() {
+                let rng = &mut rand::thread_rng();
+                *wander = Timer::from_seconds(rng.gen_range(2.0..=4.0), TimerMode::Once);
+                brain.facing = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0));
+            }
+        }
+        brain.movement = match locomotion {
+            Locomotion::Walker { .. } if perception.obstacle => Vec2::ZERO,
+            _ => brain.facing,
+        };
+        if perception.sees_player {
+            brain.set_state(AiState::Aggro);
+        }
+    }
+}
+
+fn chase(
+    mut query: Query<
+        (&GlobalTransform, &mut Brain, &Perception, &Locomotion),
+        (With<Chase>, Without<Dead>, Without<Stunned>),
+    >,
+) {
+    for (transform, mut brain, perception, locomotion) in query.iter_mut() {
+        if brain.state != AiState::Aggro {
+            continue;
+        }
+        let pos = transform.translation().xy();
+        if perception.player_distance > perception.forget && !perception.sees_player {
+            brain.set_state(AiState::Roaming);
+            continue;
+        }
+        match locomotion {
+            Locomotion::Walker { .. } => {
+                brain.facing = Vec2::new((perception.player_pos.x - pos.x).signum(), 0.0);
+                if !perception.obstacle {
+                    brain.movement = brain.facing;
+                }
+            }
+            Locomotion::Flyer { .. } => {
+                brain.facing = perception.to_player(pos);
+                brain.movement = steering::arrive(pos, perception.player_pos, 150.0);
+            }
+        }
+    }
+}
+
+fn navigate(
+    mut query: Query<
+        (
+            &GlobalTransform,
+            &mut Brain,
+            &mut Navigator,
+            &Perception,
+            &Locomotion,
+        ),
+        (Without<Dead>, Without<Stunned>),
+    >,
+    graphs: Query<(&NavGraph, &GlobalTransform)>,
+    time: Res<Time>,
+) {
+    for (transform, mut brain, mut navigator, perception, locomotion) in query.iter_mut() {
+        let Locomotion::Walker {
+            float_height, jump, ..
+        } = locomotion
+        else {
+            continue;
+        };
+        if brain.state != AiState::Aggro {
+            navigator.path.clear();
+            continue;
+        }
+        let pos = transform.translation().xy();
+        let feet = pos - Vec2::Y * *float_height;
+        navigator.repath.tick(time.delta());
+        if navigator.repath.just_finished() {
+            let target = match perception.sees_player {
+                true => perception.player_pos,
+                false => perception.last_seen.unwrap_or(perception.player_pos),
+            };
+            navigator.path = graphs
+                .iter()
+                .find_map(|(graph, level)| {
+                    let origin = level.translation().xy();
+                    graph
+                        .contains(feet - origin)
+                        .then(|| graph.find_path(origin, feet, target, *jump))
+                        .flatten()
+                })
+                .unwrap_or_default();
+        }
+        while navigator.path.first().is_some_and(|step| {
+            (feet.x - step.to.x).abs() < 32.0 && (feet.y - step.to.y).abs() < 24.0
+        }) {
+            navigator.path.remove(0);
+        }
+        let Some(step) = navigator.path.first().copied() else {
+            continue;
+        };
+        let dir = (step.to.x - step.from.x).signum();
+        let on_source = (feet.y - step.from.y).abs() < 24.0;
+        let at_takeoff = (feet.x - step.from.x) * dir >= -16.0;
+        let target_x = match on_source && !at_takeoff {
+            true => step.from.x,
+            false => step.to.x,
+        };
+        brain.facing = Vec2::new((target_x - pos.x).signum(), 0.0);
+        brain.movement = brain.facing;
+        let rising = feet.y < step.to.y.max(step.from.y) + 8.0;
+        if step.kind == LinkKind::Jump && ((on_source && at_takeoff) || (!on_source && rising)) {
+            brain.jump = Some(step.height().min(*jump) + 40.0);
+        }
+    }
+}
+
+fn melee(
+    mut query: Query<
+        (
+            &GlobalTransform,
+            &mut Brain,
+            &mut Melee,
+            &mut Swing,
+            &Perception,
+            &mut AnimationConfig,
+            &Sprite,
+            Option<&mut TnuaController>,
+            Option<&Squad>,
+            Entity,
+        ),
+        (Without<Dead>, Without<Stunned>),
+    >,
+    player: Single<Entity, With<Player>>,
+    spatial_query: SpatialQuery,
+    mut tactics: SquadTactics,
+    mut damage: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    let playerentity = *player;
+    for (
+        transform,
+        mut brain,
+        mut melee,
+        mut swing,
+        perception,
+        mut animconf,
+        sprite,
+        controller,
+        squad,
+        entity,
+    ) in query.iter_mut()
+    {
+        melee.cooldown.tick(time.delta());
+        let pos = transform.translation().xy();
+        match brain.state {
+            AiState::Aggro => {
+                if perception.player_distance < melee.trigger
+                    && melee.cooldown.finished()
+                    && tactics.claim(squad)
+                {
+                    melee.cooldown.reset();
+                    swing.phase = Some(AttackPhase::Windup);
+                    swing.hit = false;
+                    brain.movement = Vec2::ZERO;
+                    brain.set_state(AiState::Attacking);
+                }
+            }
+            AiState::Attacking => {
+                if !animconf.is_playing() {
+                    swing.phase = None;
+                    brain.set_state(AiState::Aggro);
+                    continue;
+                }
+                // Until the attack animation is applied the sprite still shows the old frames.
+                let phase = match (animconf.shows(sprite), &sprite.texture_atlas) {
+                    (true, Some(atlas)) => melee.phase(atlas.index),
+                    _ => AttackPhase::Windup,
+                };
+                swing.phase = Some(phase);
+                let dir = perception.to_player(pos);
+                if phase == AttackPhase::Windup {
+                    brain.facing = Vec2::new(dir.x.signum(), 0.0);
+                }
+                if phase != AttackPhase::Active {
+                    continue;
+                }
+                if let (Some(distance), Some(mut controller)) = (melee.dash, controller) {
+                    controller.action(TnuaBuiltinDash {
+                        displacement: brain.facing.extend(0.0) * distance,
+                        speed: 600.0,
+                        allow_in_air: true,
+                        acceleration: 800.0,
+                        brake_acceleration: Float::INFINITY,
+                        brake_to_speed: 250.0,
+                        ..TnuaBuiltinDash::default()
+                    });
+                }
+                let res = spatial_query.shape_intersections(
+                    &Collider::circle(melee.reach),
+                    pos,
+                    0.0,
+                    &SpatialQueryFilter::default(),
+                );
+                if !swing.hit && res.contains(&playerentity) {
+                    swing.hit = true;
+                    damage.write(DamageEvent {
+                        target: playerentity,
+                        source: Some(entity),
+                        amount: melee.damage,
+                        kind: DamageKind::Physical,
+                        knockback: dir * melee.knockback,
+                        stun: melee.stun,
+                        inflicts_status: true,
+                        lethal: false,
+                    });
+                }
+            }
+            AiState::Roaming => (),
+        }
+    }
+}
+
+/// Flashes enemies winding up a melee attack and marks the ground the attack is about to hit.
+fn telegraph(
+    mut query: Query<(
+        Entity,
+        &Melee,
+        &mut Swing,
+        &mut Sprite,
+        Option<&Locomotion>,
+        Has<Dead>,
+        Has<Stunned>,
+    )>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, melee, mut swing, mut sprite, locomotion, dead, stunned) in query.iter_mut() {
+        if swing.phase != Some(AttackPhase::Windup) || dead || stunned {
+            if let Some(marker) = swing.marker.take() {
+                commands.entity(marker).despawn();
+            }
+            continue;
+        }
+        if (time.elapsed_secs() * TELEGRAPH_FLASH).sin() > 0.0 {
+            sprite.color = TELEGRAPH_TINT;
+        }
+        if swing.marker.is_none() {
+            let ground = match locomotion {
+                Some(Locomotion::Walker { float_height, .. }) => -*float_height,
+                _ => 0.0,
+            };
+            let marker = commands
+                .spawn((
+                    Name::new("Telegraph"),
+                    Sprite::from_color(TELEGRAPH_MARKER, Vec2::new(melee.reach * 2.0, 6.0)),
+                    Transform::from_xyz(0.0, ground, -0.5),
+                    ChildOf(entity),
+                ))
+                .id();
+            swing.marker = Some(marker);
+        }
+    }
+}
+
+fn ranged(
+    mut query: Query<
+        (
+            &GlobalTransform,
+            &mut Brain,
+            &mut Ranged,
+            &Perception,
+            &Sprite,
+            Option<&Squad>,
+            Entity,
+        ),
+        (Without<Dead>, Without<Stunned>),
+    >,
+    mut projectiles: ProjectileSpawner,
+    mut tactics: SquadTactics,
+    time: Res<Time>,
+) {
+    for (transform, mut brain, mut ranged, perception, sprite, squad, entity) in query.iter_mut() {
+        ranged.cooldown.tick(time.delta());
+        let pos = transform.translation();
+        match brain.state {
+            AiState::Aggro => {
+                if perception.player_distance < ranged.range && tactics.claim(squad) {
+                    ranged.cooldown.reset();
+                    brain.set_state(AiState::Attacking);
+                }
+            }
+            AiState::Attacking => {
+                ranged.aim = perception.to_player(pos.xy());
+                if perception.player_distance > ranged.leave {
+                    brain.set_state(AiState::Aggro);
+                    continue;
+                }
+                let frame = sprite.texture_atlas.as_ref().map(|atlas| atlas.index);
+                if frame != Some(ranged.fire_frame) || !ranged.cooldown.finished() {
+                    continue;
+                }
+                ranged.cooldown.reset();
+                let size = Vec2::splat(75.0);
+                let atlas = TextureAtlas {
+                    layout: ranged.atlas.clone(),
+                    index: 0,
+                };
+                projectiles.spawn((
+                    StateScoped(Screen::Gameplay),
+                    Transform::from_translation(pos),
+                    Sprite {
+                        image: ranged.sprite.clone(),
+                        texture_atlas: Some(atlas.clone()),
+                        custom_size: Some(size),
+                        ..Default::default()
+                    },
+                    AnimationConfig::new(
+                        ranged.frames.0,
+                        ranged.frames.1,
+                        4,
+                        true,
+                        true,
+                        Some(atlas),
+                        ranged.sprite.clone(),
+                    ),
+                    Directional {
+                        flipdir: true,
+                        ..Default::default()
+                    },
+                    Projectile {
+                        velocity: ranged.aim * ranged.speed,
+                        dmg: ranged.damage,
+                        kind: ranged.kind,
+                        source: Some(entity),
+                        knockback: ranged.knockback,
+                        stun: ranged.stun,
+                        target: ProjectileTarget::Player,
+                        size,
+                        ..Default::default()
+                    },
+                ));
+            }
+            AiState::Roaming => (),
+        }
+    }
+}
+
+fn keep_distance(
+    mut query: Query<
+        (&mut Brain, &KeepDistance, &Perception, &GlobalTransform),
+        (Without<Dead>, Without<Stunned>),
+    >,
+) {
+    for (mut brain, keep, perception, transform) in query.iter_mut() {
+        if brain.state == AiState::Attacking && perception.player_distance < keep.min {
+            brain.facing = steering::flee(transform.translation().xy(), perception.player_pos);
+            brain.movement = brain.facing;
+        }
+    }
+}
+
+fn drive(
+    mut query: Query<(&Brain, &Locomotion, &mut TnuaController), (Without<Dead>, Without<Stunned>)>,
+) {
+    for (brain, locomotion, mut controller) in query.iter_mut() {
+        match locomotion {
+            Locomotion::Walker {
+                speed,
+                float_height,
+                ..
+            } => {
+                if let Some(height) = brain.jump {
+                    controller.action(TnuaBuiltinJump {
+                        height,
+                        ..TnuaBuiltinJump::default()
+                    });
+                }
+                controller.basis(TnuaBuiltinWalk {
+                    desired_velocity: Vec3::new(brain.movement.x, 0.0, 0.0) * *speed,
+                    acceleration: Float::INFINITY,
+                    // The `float_height` must be greater (even if by little) from the distance
+                    // between the character's center and the lowest point of its collider.
+                    float_height: *float_height,
+                    ..TnuaBuiltinWalk::default()
+                });
+            }
+            Locomotion::Flyer { .. } => (),
+        }
+    }
+}
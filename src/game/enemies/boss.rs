@@ -0,0 +1,531 @@
+//! Boss encounters. Any level containing a `boss` entity is a boss arena: once the player enters it
+//! the `boss_door`s of the level are sealed until the boss dies. The boss fights in phases
+//! picked from its remaining health and, from the second phase on, tries to rewind the damage it
+//! took unless the player interrupts it.
+
+use std::collections::VecDeque;
+
+use avian2d::prelude::{Collider, CollisionLayers, LayerMask, LockedAxes, RigidBody};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{
+    EntityInstance, LdtkEntity, LevelIid, app::LdtkEntityAppExt, assets::LdtkProject,
+};
+use bevy_tnua::prelude::TnuaController;
+use bevy_tnua_avian2d::TnuaAvian2dSensorShape;
+
+use crate::{
+    AgedSystems, AppSystems,
+    game::{
+        age::{Dead, Timed},
+        animate::{AnimationConfig, Directional},
+        enemies::{
+            Enemy,
+            ai::{AiState, Brain, Chase, Locomotion, Melee, Navigator, Patrol, Perception},
+            ghost::GhostAssets,
+            knight::KnightAssets,
+            stats::{EnemyScaling, EnemyStats},
+        },
+        health::{DamageKind, DamageTaken, Health, Status, Stunned},
+        layers::GameLayer,
+        player::Player,
+        projectile::{Projectile, ProjectileSpawner, ProjectileTarget},
+        world::collider::WallCollider,
+        worldgen::LevelAssets,
+        ysort::{ENTITY_LAYER, YSort},
+    },
+    screens::Screen,
+};
+
+/// Knockback speed of the boss' dash attack.
+const ATTACK_KNOCKBACK: f32 = 450.0;
+/// Seconds the player is staggered for after being hit by the dash attack.
+const ATTACK_STUN: f32 = 0.4;
+/// How far back the boss can rewind its health in seconds.
+const REWIND_WINDOW: f32 = 5.0;
+/// Seconds the boss has to channel a rewind.
+const REWIND_CHANNEL: f32 = 2.0;
+/// Fraction of its max health the player has to deal during the channel to interrupt a rewind.
+const REWIND_INTERRUPT: f32 = 0.08;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_ldtk_entity_for_layer::<BossBundle>("enemies", "boss");
+    app.register_ldtk_entity_for_layer::<DoorBundle>("functional", "boss_door");
+
+    app.add_systems(
+        Update,
+        init_boss
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::WorldGen)),
+    );
+    app.add_systems(
+        Update,
+        (
+            wake_boss,
+            update_phase,
+            rewind_damage,
+            volley,
+            animate_boss,
+            seal_doors,
+        )
+            .chain()
+            .in_set(AgedSystems)
+            .in_set(AppSystems::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+struct BossPhase {
+    /// The phase starts once the boss' health drops to this fraction.
+    threshold: f32,
+    speed: f32,
+    attack_cooldown: f32,
+    /// Number of projectiles fired in a ring and the seconds between rings.
+    volley: Option<(usize, f32)>,
+    rewinds: bool,
+}
+
+const PHASES: [BossPhase; 3] = [
+    BossPhase {
+        threshold: 1.0,
+        speed: 1.0,
+        attack_cooldown: 1.0,
+        volley: None,
+        rewinds: false,
+    },
+    BossPhase {
+        threshold: 0.66,
+        speed: 1.2,
+        attack_cooldown: 0.75,
+        volley: Some((6, 3.0)),
+        rewinds: true,
+    },
+    BossPhase {
+        threshold: 0.33,
+        speed: 1.5,
+        attack_cooldown: 0.5,
+        volley: Some((10, 2.0)),
+        rewinds: true,
+    },
+];
+
+/// The boss whose health bar is shown in the HUD. Added once the boss notices the player.
+#[derive(Component)]
+pub struct ActiveBoss;
+
+#[derive(Clone, Default, Component)]
+struct Boss {
+    phase: usize,
+    /// The arena level the boss belongs to.
+    level: Option<Entity>,
+    /// Size of the arena level in pixels.
+    arena: Vec2,
+    speed: f32,
+    attack_cooldown: f32,
+    volley: Timer,
+    /// Sampled health over the last [`REWIND_WINDOW`] seconds.
+    history: VecDeque<(f32, f32)>,
+    elapsed: f32,
+    rewind_cooldown: Timer,
+    rewind: Option<Rewind>,
+}
+
+#[derive(Clone)]
+struct Rewind {
+    channel: Timer,
+    /// Health the boss returns to if the rewind finishes.
+    restore: f32,
+    /// Damage taken since the rewind started.
+    taken: f32,
+    aura: Entity,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+struct BossBundle {
+    boss: Boss,
+}
+
+/// A doorway of a boss arena, sealed while the player is in the level with the boss alive.
+#[derive(Clone, Default, Component)]
+struct Door {
+    size: Vec2,
+    sealed: bool,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+struct DoorBundle {
+    #[with(door_from_instance)]
+    door: Door,
+}
+
+fn door_from_instance(entity_instance: &EntityInstance) -> Door {
+    Door {
+        size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
+        sealed: false,
+    }
+}
+
+fn init_boss(
+    mut query: Query<(Entity, &mut Transform, Option<&EntityInstance>), Added<Boss>>,
+    parents: Query<&ChildOf>,
+    levels: Query<&LevelIid>,
+    scaling: EnemyScaling,
+    mut commands: Commands,
+    assets: Res<KnightAssets>,
+    level_assets: Res<LevelAssets>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    for (entity, mut transform, instance) in query.iter_mut() {
+        let Ok(mut command) = commands.get_entity(entity) else {
+            continue;
+        };
+        let ldtk_project = ldtk_project_assets
+            .get(level_assets.worlddata.id())
+            .expect("Project should be loaded if level has spawned");
+        let level = parents
+            .iter_ancestors(entity)
+            .find(|ancestor| levels.contains(*ancestor));
+        let arena = level
+            .and_then(|level| levels.get(level).ok())
+            .and_then(|iid| {
+                ldtk_project
+                    .as_standalone()
+                    .get_loaded_level_by_iid(&iid.to_string())
+            })
+            .map_or(Vec2::ZERO, |level| {
+                Vec2::new(*level.px_wid() as f32, *level.px_hei() as f32)
+            });
+        transform.translation.z = 2.0;
+        let stats = scaling.stats(
+            entity,
+            EnemyStats::new(600.0, 90.0, 25.0, 700.0, 1.4),
+            instance,
+        );
+        let atlas = TextureAtlas {
+            layout: assets.atlas_walk.clone(),
+            index: 0,
+        };
+        command.insert((
+            Boss {
+                level,
+                arena,
+                speed: stats.speed,
+                attack_cooldown: stats.attack_cooldown,
+                rewind_cooldown: Timer::from_seconds(8.0, TimerMode::Once),
+                ..Default::default()
+            },
+            Health::new(stats.health)
+                .with_resistance(DamageKind::Time, 0.75)
+                .with_resistance(DamageKind::Physical, 0.2),
+            Timed::default(),
+            Enemy,
+            Sprite {
+                image: assets.sprite_walk.clone(),
+                texture_atlas: Some(atlas.clone()),
+                custom_size: Some(Vec2::new(200.0, 140.0)),
+                ..Default::default()
+            },
+            YSort::new(ENTITY_LAYER, 128.0),
+            RigidBody::Dynamic,
+            Collider::capsule(32.0, 64.0),
+            CollisionLayers::new(GameLayer::Enemy, LayerMask::ALL),
+            TnuaController::default(),
+            TnuaAvian2dSensorShape(Collider::rectangle(62.0, 0.0)),
+            LockedAxes::ROTATION_LOCKED,
+            Name::new("Time Knight"),
+            AnimationConfig::new(0, 3, 8, true, true, Some(atlas), assets.sprite_walk.clone()),
+            Directional {
+                flipdir: true,
+                ..Default::default()
+            },
+        ));
+        command.insert((
+            Brain::facing(Vec2::NEG_X),
+            Perception::new(stats.aggro_range, 4000.0),
+            Locomotion::Walker {
+                speed: stats.speed,
+                float_height: 66.0,
+                jump: 100.0,
+            },
+            Patrol::default(),
+            Chase,
+            Navigator::default(),
+            Melee {
+                trigger: 300.0,
+                reach: 90.0,
+                active: (4, 6),
+                damage: stats.damage,
+                knockback: ATTACK_KNOCKBACK,
+                stun: ATTACK_STUN,
+                dash: Some(400.0),
+                cooldown: Timer::from_seconds(stats.attack_cooldown, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+fn wake_boss(
+    query: Query<(Entity, &Brain, Has<ActiveBoss>, Has<Dead>), With<Boss>>,
+    mut commands: Commands,
+) {
+    for (entity, brain, active, dead) in query.iter() {
+        if dead && active {
+            commands.entity(entity).remove::<ActiveBoss>();
+        } else if !dead && !active && brain.state != AiState::Roaming {
+            commands.entity(entity).insert(ActiveBoss);
+        }
+    }
+}
+
+fn update_phase(
+    mut query: Query<
+        (&mut Boss, &mut Health, &mut Locomotion, &mut Melee),
+        (With<ActiveBoss>, Without<Dead>),
+    >,
+) {
+    for (mut boss, mut health, mut locomotion, mut melee) in query.iter_mut() {
+        let percent = health.get_percent();
+        let phase = PHASES
+            .iter()
+            .rposition(|phase| percent <= phase.threshold)
+            .unwrap_or_default()
+            .max(boss.phase);
+        let channeling = boss.rewind.is_some();
+        if let Locomotion::Walker { speed, .. } = &mut *locomotion {
+            *speed = if channeling {
+                0.0
+            } else {
+                boss.speed * PHASES[phase].speed
+            };
+        }
+        if channeling {
+            melee.cooldown.reset();
+        }
+        if phase == boss.phase {
+            continue;
+        }
+        boss.phase = phase;
+        let cooldown = boss.attack_cooldown * PHASES[phase].attack_cooldown;
+        melee
+            .cooldown
+            .set_duration(std::time::Duration::from_secs_f32(cooldown));
+        if let Some((_, interval)) = PHASES[phase].volley {
+            boss.volley = Timer::from_seconds(interval, TimerMode::Repeating);
+        }
+        // A short pause telegraphs the phase change.
+        health.apply_status(Status::Staggered, 0.6);
+    }
+}
+
+fn rewind_damage(
+    mut query: Query<
+        (Entity, &mut Boss, &mut Health, Has<Stunned>),
+        (With<ActiveBoss>, Without<Dead>),
+    >,
+    mut taken: EventReader<DamageTaken>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    let taken: Vec<&DamageTaken> = taken.read().collect();
+    for (entity, mut boss, mut health, stunned) in query.iter_mut() {
+        let dt = time.delta_secs();
+        boss.elapsed += dt;
+        let now = boss.elapsed;
+        boss.history.push_back((now, health.health));
+        while boss
+            .history
+            .front()
+            .is_some_and(|(sampled, _)| now - sampled > REWIND_WINDOW)
+        {
+            boss.history.pop_front();
+        }
+        boss.rewind_cooldown.tick(time.delta());
+
+        let Some(mut rewind) = boss.rewind.take() else {
+            let restore = boss.history.front().map_or(0.0, |(_, health)| *health);
+            if PHASES[boss.phase].rewinds
+                && boss.rewind_cooldown.finished()
+                && !stunned
+                && restore - health.health >= health.max_health() * 0.05
+            {
+                let aura = commands
+                    .spawn((
+                        Name::new("RewindAura"),
+                        Sprite::from_color(Color::srgba(0.4, 0.6, 1.0, 0.35), Vec2::splat(180.0)),
+                        Transform::from_xyz(0.0, 0.0, -0.1),
+                        ChildOf(entity),
+                    ))
+                    .id();
+                boss.rewind = Some(Rewind {
+                    channel: Timer::from_seconds(REWIND_CHANNEL, TimerMode::Once),
+                    restore,
+                    taken: 0.0,
+                    aura,
+                });
+            }
+            continue;
+        };
+        rewind.taken += taken
+            .iter()
+            .filter(|event| event.entity == entity)
+            .map(|event| event.amount)
+            .sum::<f32>();
+        rewind.channel.tick(time.delta());
+        let interrupted = stunned || rewind.taken >= health.max_health() * REWIND_INTERRUPT;
+        if !interrupted && !rewind.channel.finished() {
+            boss.rewind = Some(rewind);
+            continue;
+        }
+        commands.entity(rewind.aura).despawn();
+        boss.rewind_cooldown.reset();
+        if interrupted {
+            health.apply_status(Status::Staggered, 1.0);
+        } else {
+            let amount = rewind.restore - health.health;
+            health.heal(amount);
+            boss.history.clear();
+        }
+    }
+}
+
+fn volley(
+    mut query: Query<(Entity, &GlobalTransform, &mut Boss), (With<ActiveBoss>, Without<Dead>)>,
+    mut projectiles: ProjectileSpawner,
+    assets: Res<GhostAssets>,
+    time: Res<Time>,
+) {
+    for (entity, transform, mut boss) in query.iter_mut() {
+        let Some((count, _)) = PHASES[boss.phase].volley else {
+            continue;
+        };
+        boss.volley.tick(time.delta());
+        if !boss.volley.just_finished() || boss.rewind.is_some() {
+            continue;
+        }
+        for i in 0..count {
+            let dir = Vec2::from_angle(i as f32 / count as f32 * std::f32::consts::TAU);
+            let size = Vec2::splat(50.0);
+            let atlas = TextureAtlas {
+                layout: assets.atlas_proj.clone(),
+                index: 0,
+            };
+            projectiles.spawn((
+                StateScoped(Screen::Gameplay),
+                Transform::from_translation(transform.translation()),
+                Sprite {
+                    image: assets.sprite_proj.clone(),
+                    texture_atlas: Some(atlas.clone()),
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                AnimationConfig::new(0, 8, 4, true, true, Some(atlas), assets.sprite_proj.clone()),
+                Directional {
+                    flipdir: true,
+                    ..Default::default()
+                },
+                Projectile {
+                    velocity: dir * 160.0,
+                    dmg: 10.0,
+                    kind: DamageKind::Time,
+                    source: Some(entity),
+                    knockback: 100.0,
+                    stun: 0.1,
+                    target: ProjectileTarget::Player,
+                    size,
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+}
+
+fn animate_boss(
+    mut query: Query<
+        (&mut Brain, &mut AnimationConfig),
+        (With<Boss>, Without<Dead>, Without<Stunned>),
+    >,
+    assets: Res<KnightAssets>,
+) {
+    for (mut brain, mut animconf) in query.iter_mut() {
+        if !brain.dirty {
+            continue;
+        }
+        brain.dirty = false;
+        match brain.state {
+            AiState::Roaming | AiState::Aggro => {
+                animconf.update_sprite(
+                    Some(TextureAtlas {
+                        layout: assets.atlas_walk.clone(),
+                        index: 0,
+                    }),
+                    assets.sprite_walk.clone(),
+                );
+                animconf.set_frames(0, 3);
+                animconf.set_looping(true);
+                animconf.play();
+            }
+            AiState::Attacking => {
+                animconf.update_sprite(
+                    Some(TextureAtlas {
+                        layout: assets.atlas_attack.clone(),
+                        index: 0,
+                    }),
+                    assets.sprite_attack.clone(),
+                );
+                animconf.set_frames(0, 8);
+                animconf.set_looping(false);
+                animconf.play();
+            }
+        }
+    }
+}
+
+/// Seals the doors of the arena the player is in while its boss is alive and opens them again once
+/// it is gone.
+fn seal_doors(
+    mut doors: Query<(Entity, &GlobalTransform, &mut Door)>,
+    bosses: Query<&Boss, Without<Dead>>,
+    levels: Query<&GlobalTransform, With<LevelIid>>,
+    parents: Query<&ChildOf>,
+    player: Single<&GlobalTransform, With<Player>>,
+    mut commands: Commands,
+) {
+    let playerpos = player.translation().xy();
+    // Sealing the doors while the player stands in one would trap them inside the wall.
+    let in_doorway = doors.iter().any(|(_, transform, door)| {
+        (playerpos - transform.translation().xy())
+            .abs()
+            .cmplt(door.size / 2.0 + Vec2::splat(40.0))
+            .all()
+    });
+    for (entity, _, mut door) in doors.iter_mut() {
+        let in_arena = bosses.iter().any(|boss| {
+            let Some(level) = boss.level else {
+                return false;
+            };
+            // Levels are placed by their bottom left corner.
+            parents.iter_ancestors(entity).any(|a| a == level)
+                && levels.get(level).is_ok_and(|transform| {
+                    let min = transform.translation().xy();
+                    Rect::from_corners(min, min + boss.arena).contains(playerpos)
+                })
+        });
+        let sealed = in_arena && (door.sealed || !in_doorway);
+        if sealed == door.sealed {
+            continue;
+        }
+        door.sealed = sealed;
+        if sealed {
+            commands.entity(entity).insert((
+                RigidBody::Static,
+                Collider::rectangle(door.size.x, door.size.y),
+                CollisionLayers::new(GameLayer::Wall, LayerMask::ALL),
+                WallCollider,
+                Sprite::from_color(Color::srgba(0.3, 0.1, 0.5, 0.8), door.size),
+            ));
+        } else {
+            commands
+                .entity(entity)
+                .remove::<(RigidBody, Collider, CollisionLayers, WallCollider, Sprite)>();
+        }
+    }
+}
@@ -1,25 +1,22 @@
-use avian2d::{
-    parry::na::ComplexField,
-    prelude::{
-        Collider, LockedAxes, RayCaster, RigidBody, ShapeCaster, SpatialQuery, SpatialQueryFilter,
-    },
-};
+use avian2d::prelude::{Collider, CollisionLayers, LayerMask, LockedAxes, RayCaster, RigidBody};
 use bevy::prelude::*;
-use bevy_ecs_ldtk::{LdtkEntity, app::LdtkEntityAppExt};
-use bevy_tnua::{
-    TnuaAction, TnuaAnimatingState,
-    builtins::{TnuaBuiltinDash, TnuaBuiltinJumpState},
-    math::Float,
-    prelude::{TnuaBuiltinJump, TnuaBuiltinWalk, TnuaController},
-};
+use bevy_ecs_ldtk::{EntityInstance, LdtkEntity, app::LdtkEntityAppExt};
+use bevy_tnua::prelude::TnuaController;
 use bevy_tnua_avian2d::TnuaAvian2dSensorShape;
 
 use crate::{
     asset_tracking::LoadResource, game::{
-        age::{Dead, Timed}, animate::{AnimationConfig, Directional}, enemies::Enemy, health::Health, player::Player, ysort::{YSort, ENTITY_LAYER}
-    }, screens::Screen, AgedSystems, AppSystems, PausableSystems
+        age::{Dead, Timed}, animate::{AnimationConfig, Directional},
+        enemies::{Enemy, ai::{AiState, Brain, Chase, Locomotion, Melee, Navigator, Patrol, Perception}, stats::{EnemyScaling, EnemyStats}},
+        layers::GameLayer, health::{DamageKind, Health, Stunned}, ysort::{YSort, ENTITY_LAYER}
+    }, screens::Screen, AgedSystems, AppSystems
 };
 
+/// Knockback speed of the dash attack.
+const ATTACK_KNOCKBACK: f32 = 350.0;
+/// Seconds the player is staggered for after being hit by the dash attack.
+const ATTACK_STUN: f32 = 0.35;
+
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<KnightAssets>();
     app.register_ldtk_entity_for_layer::<KnightBundle>("enemies", "knight");
@@ -32,13 +29,6 @@ pub(super) fn plugin(app: &mut App) {
             .in_set(AppSystems::Update)
             .run_if(in_state(Screen::WorldGen)),
     );
-    app.add_systems(
-        Update,
-        update_knight
-            .in_set(AgedSystems)
-            .in_set(AppSystems::Update)
-            .run_if(in_state(Screen::Gameplay)),
-    );
     app.add_systems(
         Update,
         animate_knight
@@ -48,32 +38,8 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-#[derive(Clone, Default, Debug, PartialEq)]
-enum State {
-    #[default]
-    Roaming,
-    Aggro,
-    Attacking,
-}
-
-#[derive(Clone, Component)]
-struct Knight {
-    state: State,
-    dir: bool,
-    dirty: bool,
-    attackcooldown: Timer,
-}
-
-impl Default for Knight {
-    fn default() -> Self {
-        Self {
-            state: Default::default(),
-            dir: Default::default(),
-            dirty: Default::default(),
-            attackcooldown: Timer::from_seconds(1.0, TimerMode::Once),
-        }
-    }
-}
+#[derive(Clone, Default, Component)]
+struct Knight;
 
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
@@ -110,21 +76,29 @@ struct KnightBundle {
 }
 
 fn init_knight(
-    mut query: Query<(Entity, &mut Transform), Added<Knight>>,
+    mut query: Query<(Entity, &mut Transform, Option<&EntityInstance>), Added<Knight>>,
+    scaling: EnemyScaling,
     mut commands: Commands,
     assets: Res<KnightAssets>,
 ) {
-    for (entity, mut transform) in query.iter_mut() {
+    for (entity, mut transform, instance) in query.iter_mut() {
         let Ok(mut command) = commands.get_entity(entity) else {
             continue;
         };
         transform.translation.z = 2.0;
+        let stats = scaling.stats(
+            entity,
+            EnemyStats::new(120.0, 100.0, 15.0, 500.0, 1.0),
+            instance,
+        );
         let atlas = TextureAtlas {
             layout: assets.atlas_walk.clone(),
             index: 0,
         };
         command.insert((
-            Health::new(120.0),
+            Health::new(stats.health)
+                .with_resistance(DamageKind::Fire, 0.3)
+                .with_resistance(DamageKind::Current, -0.5),
             Timed::default(),
             Enemy,
             Sprite {
@@ -136,6 +110,7 @@ fn init_knight(
             YSort::new(ENTITY_LAYER, 64.0),
             RigidBody::Dynamic,
             Collider::capsule(16.0, 32.0),
+            CollisionLayers::new(GameLayer::Enemy, LayerMask::ALL),
             // TnuaAnimatingState::<AnimationState>::default(),
             TnuaController::default(),
             TnuaAvian2dSensorShape(Collider::rectangle(31.0, 0.0)),
@@ -148,130 +123,45 @@ fn init_knight(
             },
             RayCaster::new(Vec2::ZERO, Dir2::X),
         ));
-    }
-}
-
-fn update_knight(
-    mut query: Query<(&GlobalTransform, &mut Knight, &mut TnuaController, Entity), Without<Dead>>,
-    mut player_query: Query<(&GlobalTransform, Entity, &mut Health), With<Player>>,
-    spatial_query: SpatialQuery,
-    time: Res<Time>,
-) {
-    let Ok((player, playerentity, mut health)) = player_query.single_mut() else {
-        return;
-    };
-    let playerpos = player.translation().xy();
-    for (transform, mut knight, mut controller, entity) in query.iter_mut() {
-        knight.attackcooldown.tick(time.delta());
-        let knightpos = transform.translation().xy();
-        let mut sees_obstacle = false;
-        let mut sees_player = false;
-        let mut dir = match knight.dir {
-            true => Vec2::new(1.0, 0.0),
-            false => Vec2::new(-1.0, 0.0),
-        };
-        let filter = SpatialQueryFilter::default().with_excluded_entities([entity, playerentity]);
-        let rayhit =
-            spatial_query.cast_ray(knightpos + dir * 25.0, Dir2::NEG_Y, 50.0, true, &filter);
-        if let Some(rayhit) = rayhit {
-            if rayhit.distance <= 10.0 || rayhit.distance >= 35.0 {
-                sees_obstacle = true;
-            }
-        } else {
-            sees_obstacle = true;
-        }
-        let filter = SpatialQueryFilter::default().with_excluded_entities([entity]);
-        if let Ok(ndir) = Dir2::new(playerpos - knightpos) {
-            let rayhit = spatial_query.cast_ray(knightpos, ndir, 500.0, true, &filter);
-            if let Some(rayhit) = rayhit {
-                sees_player = rayhit.entity == playerentity;
-            }
-        }
-        let mut nextstate = knight.state.clone();
-        // println!("My State is: {:?}", Knight.state);
-        match knight.state {
-            State::Roaming => {
-                if sees_obstacle {
-                    knight.dir = !knight.dir;
-                    dir.x = 0.0;
-                }
-                if sees_player {
-                    nextstate = State::Aggro;
-                }
-            }
-            State::Aggro => {
-                knight.dir = (playerpos.x - knightpos.x) > 0.0;
-                let distance = knightpos.distance_squared(playerpos);
-                if distance > (2000.0).powi(2) && !sees_player {
-                    nextstate = State::Roaming;
-                }
-                if sees_obstacle {
-                    dir.x = 0.0;
-                }
-                if distance < (200.0).powi(2) && knight.attackcooldown.finished() {
-                    knight.attackcooldown.reset();
-                    nextstate = State::Attacking;
-                    dir.x = 0.0;
-                }
-            }
-            State::Attacking => {
-                let dir = (playerpos - knightpos).normalize();
-                knight.dir = (playerpos.x - knightpos.x) > 0.0;
-                controller.action(TnuaBuiltinDash {
-                    displacement: Vec3::new(dir.x, dir.y, 0.0) * 300.0,
-                    speed: 600.0,
-                    allow_in_air: true,
-                    acceleration: 800.0,
-                    // acceleration: Float::INFINITY,
-                    brake_acceleration: Float::INFINITY,
-                    brake_to_speed: 250.0,
-                    ..TnuaBuiltinDash::default()
-                });
-                let res = spatial_query.shape_intersections(
-                    &Collider::circle(50.0),
-                    knightpos,
-                    0.0,
-                    &SpatialQueryFilter::default(),
-                );
-                if res.contains(&playerentity) {
-                    health.damage(15.0);
-                }
-                continue;
-                // dir.x = 0.0;
-            }
-        }
-        if nextstate != knight.state {
-            knight.state = nextstate;
-            knight.dirty = true;
-        }
-        controller.basis(TnuaBuiltinWalk {
-            // The `desired_velocity` determines how the character will move.
-            desired_velocity: Vec3::new(dir.x, 0.0, 0.0) * 100.0,
-            acceleration: Float::INFINITY,
-            // The `float_height` must be greater (even if by little) from the distance between the
-            // character's center and the lowest point of its collider.
-            float_height: 33.0,
-            // `TnuaBuiltinWalk` has many other fields for customizing the movement - but they have
-            // sensible defaults. Refer to the `TnuaBuiltinWalk`'s documentation to learn what they do.
-            ..TnuaBuiltinWalk::default()
-        });
+        command.insert((
+            Brain::facing(Vec2::NEG_X),
+            Perception::new(stats.aggro_range, 2000.0),
+            Locomotion::Walker {
+                speed: stats.speed,
+                float_height: 33.0,
+                jump: 140.0,
+            },
+            Patrol::default(),
+            Chase,
+            Navigator::default(),
+            Melee {
+                trigger: 200.0,
+                reach: 50.0,
+                active: (4, 6),
+                damage: stats.damage,
+                knockback: ATTACK_KNOCKBACK,
+                stun: ATTACK_STUN,
+                dash: Some(300.0),
+                cooldown: Timer::from_seconds(stats.attack_cooldown, TimerMode::Once),
+            },
+        ));
     }
 }
 
 fn animate_knight(
-    mut query: Query<(&mut Knight, &mut AnimationConfig, &mut Sprite), Without<Dead>>,
+    mut query: Query<
+        (&mut Brain, &mut AnimationConfig),
+        (With<Knight>, Without<Dead>, Without<Stunned>),
+    >,
     assets: Res<KnightAssets>,
 ) {
-    for (mut Knight, mut animconf, mut sprite) in query.iter_mut() {
-        if Knight.state == State::Attacking && !animconf.is_playing() {
-            Knight.state = State::Aggro;
-        }
-        if !Knight.dirty {
+    for (mut brain, mut animconf) in query.iter_mut() {
+        if !brain.dirty {
             continue;
         }
-        Knight.dirty = false;
-        match Knight.state {
-            State::Roaming | State::Aggro => {
+        brain.dirty = false;
+        match brain.state {
+            AiState::Roaming | AiState::Aggro => {
                 animconf.update_sprite(
                     Some(TextureAtlas {
                         layout: assets.atlas_walk.clone(),
@@ -283,7 +173,7 @@ fn animate_knight(
                 animconf.set_looping(true);
                 animconf.play();
             }
-            State::Attacking => {
+            AiState::Attacking => {
                 animconf.update_sprite(
                     Some(TextureAtlas {
                         layout: assets.atlas_attack.clone(),
@@ -1,12 +1,25 @@
 use bevy::prelude::*;
 
+mod ai;
+pub(crate) mod boss;
 pub(crate) mod ghost;
 mod knight;
+mod squad;
+mod stats;
 mod statue;
+mod steering;
 
 #[derive(Clone, Default, Component)]
 pub struct Enemy;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((statue::plugin, knight::plugin, ghost::plugin));
+    app.add_plugins((
+        ai::plugin,
+        squad::plugin,
+        stats::plugin,
+        boss::plugin,
+        statue::plugin,
+        knight::plugin,
+        ghost::plugin,
+    ));
 }
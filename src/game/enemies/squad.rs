@@ -0,0 +1,202 @@
+//! Coordination between the enemies of a room. An enemy that spots the player alerts the others
+//! of its room within [`ALERT_RADIUS`], flyers spread out around the player instead of stacking up
+//! and attacks are handed out through the room's [`Tactics`] so only a few enemies attack at once.
+
+use std::f32::consts::PI;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::game::{
+    age::Dead,
+    enemies::{
+        ai::{AiState, Brain, Chase, Locomotion, Perception},
+        steering,
+    },
+    health::Stunned,
+    worldgen::RoomDifficulty,
+};
+
+/// How far from the enemy that spotted the player an alert is heard.
+const ALERT_RADIUS: f32 = 700.0;
+/// How many enemies of a room may attack at the same time.
+const MAX_ATTACKERS: u32 = 2;
+/// Seconds between two enemies of a room starting an attack.
+const ATTACK_STAGGER: f32 = 0.6;
+/// Distance flanking flyers keep from the player.
+const FLANK_DISTANCE: f32 = 250.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<Alert>();
+}
+
+/// Sent when an enemy spots the player.
+#[derive(Event, Debug)]
+pub(super) struct Alert {
+    room: Entity,
+    pos: Vec2,
+    player_pos: Vec2,
+}
+
+/// The room an enemy belongs to, resolved by [`join_squad`].
+#[derive(Component, Clone, Default)]
+pub(super) struct Squad {
+    room: Option<Entity>,
+    /// Whether the enemy saw the player last frame.
+    spotted: bool,
+}
+
+/// Who in a room is attacking, kept on the room entity.
+#[derive(Component, Clone, Default)]
+pub(super) struct Tactics {
+    attackers: u32,
+    last_attack: f32,
+}
+
+/// Hands out attacks between the enemies of a room.
+#[derive(SystemParam)]
+pub(super) struct SquadTactics<'w, 's> {
+    rooms: Query<'w, 's, &'static mut Tactics>,
+    time: Res<'w, Time>,
+}
+
+impl SquadTactics<'_, '_> {
+    /// Whether a member of `squad` may start an attack now, claiming a spot if so. Enemies
+    /// outside of any room always may.
+    pub fn claim(&mut self, squad: Option<&Squad>) -> bool {
+        let Some(mut tactics) = squad
+            .and_then(|squad| squad.room)
+            .and_then(|room| self.rooms.get_mut(room).ok())
+        else {
+            return true;
+        };
+        let now = self.time.elapsed_secs();
+        if tactics.attackers >= MAX_ATTACKERS || now - tactics.last_attack < ATTACK_STAGGER {
+            return false;
+        }
+        tactics.attackers += 1;
+        tactics.last_attack = now;
+        true
+    }
+}
+
+pub(super) fn join_squad(
+    query: Query<Entity, Added<Brain>>,
+    parents: Query<&ChildOf>,
+    rooms: Query<(), With<RoomDifficulty>>,
+    mut commands: Commands,
+) {
+    for entity in query.iter() {
+        let room = parents
+            .iter_ancestors(entity)
+            .find(|ancestor| rooms.contains(*ancestor));
+        if let Some(room) = room {
+            commands.entity(room).insert_if_new(Tactics::default());
+        }
+        commands.entity(entity).insert(Squad {
+            room,
+            spotted: false,
+        });
+    }
+}
+
+pub(super) fn broadcast(
+    mut query: Query<(&GlobalTransform, &Perception, &mut Squad), Without<Dead>>,
+    mut alerts: EventWriter<Alert>,
+) {
+    for (transform, perception, mut squad) in query.iter_mut() {
+        if perception.sees_player && !squad.spotted {
+            if let Some(room) = squad.room {
+                alerts.write(Alert {
+                    room,
+                    pos: transform.translation().xy(),
+                    player_pos: perception.player_pos,
+                });
+            }
+        }
+        squad.spotted = perception.sees_player;
+    }
+}
+
+pub(super) fn alert(
+    mut alerts: EventReader<Alert>,
+    mut query: Query<
+        (&GlobalTransform, &mut Brain, &mut Perception, &Squad),
+        (Without<Dead>, Without<Stunned>),
+    >,
+) {
+    for alert in alerts.read() {
+        for (transform, mut brain, mut perception, squad) in query.iter_mut() {
+            if squad.room != Some(alert.room)
+                || brain.state != AiState::Roaming
+                || transform.translation().xy().distance(alert.pos) > ALERT_RADIUS
+            {
+                continue;
+            }
+            perception.last_seen = Some(alert.player_pos);
+            brain.set_state(AiState::Aggro);
+        }
+    }
+}
+
+/// Counts the attackers of every room for [`SquadTactics::claim`].
+pub(super) fn tally(
+    members: Query<(&Brain, &Squad), Without<Dead>>,
+    mut rooms: Query<&mut Tactics>,
+) {
+    for mut tactics in rooms.iter_mut() {
+        tactics.attackers = 0;
+    }
+    for (brain, squad) in members.iter() {
+        if brain.state != AiState::Attacking {
+            continue;
+        }
+        if let Some(mut tactics) = squad.room.and_then(|room| rooms.get_mut(room).ok()) {
+            tactics.attackers += 1;
+        }
+    }
+}
+
+/// Spreads the chasing flyers of a room over a half circle above the player.
+pub(super) fn flank(
+    mut query: Query<
+        (
+            &GlobalTransform,
+            &mut Brain,
+            &Perception,
+            &Locomotion,
+            &Squad,
+            Entity,
+        ),
+        (With<Chase>, Without<Dead>, Without<Stunned>),
+    >,
+) {
+    let flanking = |brain: &Brain, locomotion: &Locomotion| {
+        brain.state == AiState::Aggro && matches!(locomotion, Locomotion::Flyer { .. })
+    };
+    let mut flyers: Vec<(Entity, Entity)> = query
+        .iter()
+        .filter(|(_, brain, _, locomotion, _, _)| flanking(brain, locomotion))
+        .filter_map(|(_, _, _, _, squad, entity)| squad.room.map(|room| (room, entity)))
+        .collect();
+    flyers.sort();
+    for (transform, mut brain, perception, locomotion, squad, entity) in query.iter_mut() {
+        let Some(room) = squad.room else {
+            continue;
+        };
+        if !flanking(&brain, locomotion) {
+            continue;
+        }
+        let group: Vec<Entity> = flyers
+            .iter()
+            .filter(|(other, _)| *other == room)
+            .map(|(_, flyer)| *flyer)
+            .collect();
+        if group.len() < 2 {
+            continue;
+        }
+        let slot = group.iter().position(|flyer| *flyer == entity).unwrap_or(0);
+        let angle = PI * (slot as f32 + 0.5) / group.len() as f32;
+        let target = perception.player_pos + Vec2::from_angle(angle) * FLANK_DISTANCE;
+        brain.movement = steering::arrive(transform.translation().xy(), target, 150.0);
+    }
+}
@@ -0,0 +1,219 @@
+//! Enemy stats per variant. The numbered LDtk identifiers (`ghost2`, `knight3`, ...) select a
+//! tier which scales the base stats of the enemy. Every enemy entity in LDtk has the optional
+//! fields `health`, `speed`, `damage`, `aggro_range`, `attack_cooldown` (floats) and, for ghosts,
+//! `projectile` (`arcane`, `fire` or `current`), which are left empty and only set on placed
+//! entities that override their tier.
+//!
+//! On top of that enemies are scaled by the [`RoomDifficulty`] of the room they spawn in using the
+//! [`DifficultyScaling`] table.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_ldtk::{EntityInstance, prelude::LdtkFields};
+
+use crate::game::{health::DamageKind, worldgen::RoomDifficulty};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DifficultyScaling>();
+    app.init_resource::<DifficultyScaling>();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum ProjectileType {
+    Arcane,
+    Fire,
+    Current,
+}
+
+impl ProjectileType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "arcane" => Some(ProjectileType::Arcane),
+            "fire" => Some(ProjectileType::Fire),
+            "current" => Some(ProjectileType::Current),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> DamageKind {
+        match self {
+            ProjectileType::Arcane => DamageKind::Arcane,
+            ProjectileType::Fire => DamageKind::Fire,
+            ProjectileType::Current => DamageKind::Current,
+        }
+    }
+}
+
+/// How much each tier scales the base stats.
+struct Tier {
+    health: f32,
+    damage: f32,
+    speed: f32,
+    aggro_range: f32,
+    attack_cooldown: f32,
+    projectile: ProjectileType,
+}
+
+const TIERS: [Tier; 3] = [
+    Tier {
+        health: 1.0,
+        damage: 1.0,
+        speed: 1.0,
+        aggro_range: 1.0,
+        attack_cooldown: 1.0,
+        projectile: ProjectileType::Arcane,
+    },
+    Tier {
+        health: 1.5,
+        damage: 1.3,
+        speed: 1.15,
+        aggro_range: 1.2,
+        attack_cooldown: 0.8,
+        projectile: ProjectileType::Fire,
+    },
+    Tier {
+        health: 2.2,
+        damage: 1.6,
+        speed: 1.3,
+        aggro_range: 1.4,
+        attack_cooldown: 0.6,
+        projectile: ProjectileType::Current,
+    },
+];
+
+#[derive(Clone, Debug)]
+pub(super) struct EnemyStats {
+    pub health: f32,
+    pub speed: f32,
+    pub damage: f32,
+    /// How far the enemy can see the player.
+    pub aggro_range: f32,
+    pub attack_cooldown: f32,
+    pub projectile: ProjectileType,
+}
+
+impl EnemyStats {
+    pub fn new(
+        health: f32,
+        speed: f32,
+        damage: f32,
+        aggro_range: f32,
+        attack_cooldown: f32,
+    ) -> Self {
+        Self {
+            health,
+            speed,
+            damage,
+            aggro_range,
+            attack_cooldown,
+            projectile: ProjectileType::Arcane,
+        }
+    }
+
+    /// Takes the stats from the fields of the LDtk entity, falling back to the stats scaled by
+    /// the tier of its identifier, then scales them by the difficulty of the room.
+    fn for_entity(self, instance: Option<&EntityInstance>, room: &DifficultyLevel) -> Self {
+        let tier = &TIERS[instance.map_or(1, |instance| tier(&instance.identifier)) - 1];
+        let float = |field: &str, default: f32| {
+            instance
+                .and_then(|instance| instance.get_float_field(field).ok())
+                .copied()
+                .unwrap_or(default)
+        };
+        let projectile = instance
+            .and_then(|instance| instance.get_string_field("projectile").ok())
+            .and_then(|name| ProjectileType::from_name(name))
+            .unwrap_or(tier.projectile);
+        let health = float("health", self.health * tier.health);
+        let speed = float("speed", self.speed * tier.speed);
+        let damage = float("damage", self.damage * tier.damage);
+        let aggro_range = float("aggro_range", self.aggro_range * tier.aggro_range);
+        let attack_cooldown = float(
+            "attack_cooldown",
+            self.attack_cooldown * tier.attack_cooldown,
+        );
+        Self {
+            health: health * room.health,
+            speed: speed * (1.0 + (room.aggression - 1.0) * 0.5),
+            damage: damage * room.damage,
+            aggro_range: aggro_range * room.aggression,
+            attack_cooldown: attack_cooldown / room.aggression,
+            projectile,
+        }
+    }
+}
+
+/// Multipliers applied to enemies spawned in a room of a given difficulty.
+#[derive(Clone, Debug, Reflect)]
+pub struct DifficultyLevel {
+    pub health: f32,
+    pub damage: f32,
+    /// Raises how far enemies notice the player and how often they attack, and to a lesser
+    /// degree how fast they move.
+    pub aggression: f32,
+}
+
+/// Scaling per room difficulty, the first entry is used for difficulty 1 and rooms harder than
+/// the table use the last entry.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct DifficultyScaling {
+    pub levels: Vec<DifficultyLevel>,
+}
+
+impl DifficultyScaling {
+    pub fn get(&self, difficulty: i32) -> &DifficultyLevel {
+        let index = (difficulty.max(1) as usize - 1).min(self.levels.len() - 1);
+        &self.levels[index]
+    }
+}
+
+impl Default for DifficultyScaling {
+    fn default() -> Self {
+        let level = |health, damage, aggression| DifficultyLevel {
+            health,
+            damage,
+            aggression,
+        };
+        Self {
+            levels: vec![
+                level(1.0, 1.0, 1.0),
+                level(1.25, 1.15, 1.1),
+                level(1.6, 1.3, 1.25),
+                level(2.0, 1.5, 1.4),
+            ],
+        }
+    }
+}
+
+/// Resolves the [`EnemyStats`] of a freshly spawned enemy from its LDtk entity and the room it is in.
+#[derive(SystemParam)]
+pub(super) struct EnemyScaling<'w, 's> {
+    parents: Query<'w, 's, &'static ChildOf>,
+    rooms: Query<'w, 's, &'static RoomDifficulty>,
+    scaling: Res<'w, DifficultyScaling>,
+}
+
+impl EnemyScaling<'_, '_> {
+    pub fn stats(
+        &self,
+        entity: Entity,
+        base: EnemyStats,
+        instance: Option<&EntityInstance>,
+    ) -> EnemyStats {
+        let difficulty = self
+            .parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| self.rooms.get(ancestor).ok())
+            .map_or(1, |room| room.0);
+        base.for_entity(instance, self.scaling.get(difficulty))
+    }
+}
+
+/// The tier of a variant identifier, `ghost` is tier 1 and `ghost3` tier 3.
+fn tier(identifier: &str) -> usize {
+    identifier
+        .chars()
+        .last()
+        .and_then(|c| c.to_digit(10))
+        .map_or(1, |tier| (tier as usize).clamp(1, TIERS.len()))
+}
@@ -1,17 +1,7 @@
-use avian2d::{
-    parry::na::ComplexField,
-    prelude::{
-        Collider, LockedAxes, RayCaster, RigidBody, ShapeCaster, SpatialQuery, SpatialQueryFilter,
-    },
-};
+use avian2d::prelude::{Collider, CollisionLayers, LayerMask, LockedAxes, RayCaster, RigidBody};
 use bevy::prelude::*;
-use bevy_ecs_ldtk::{LdtkEntity, app::LdtkEntityAppExt};
-use bevy_tnua::{
-    TnuaAction, TnuaAnimatingState,
-    builtins::TnuaBuiltinJumpState,
-    math::Float,
-    prelude::{TnuaBuiltinJump, TnuaBuiltinWalk, TnuaController},
-};
+use bevy_ecs_ldtk::{EntityInstance, LdtkEntity, app::LdtkEntityAppExt};
+use bevy_tnua::prelude::TnuaController;
 use bevy_tnua_avian2d::TnuaAvian2dSensorShape;
 
 use crate::{
@@ -20,14 +10,23 @@ use crate::{
     game::{
         age::{Dead, Timed},
         animate::{AnimationConfig, Directional},
-        enemies::Enemy,
-        health::Health,
-        player::Player,
+        enemies::{
+            Enemy,
+            ai::{AiState, Brain, Chase, Locomotion, Melee, Navigator, Patrol, Perception},
+            stats::{EnemyScaling, EnemyStats},
+        },
+        health::{DamageKind, Health, Stunned},
+        layers::GameLayer,
         ysort::{ENTITY_LAYER, YSort},
     },
     screens::Screen,
 };
 
+/// Knockback speed of the attack.
+const ATTACK_KNOCKBACK: f32 = 250.0;
+/// Seconds the player is staggered for after being hit by the attack.
+const ATTACK_STUN: f32 = 0.25;
+
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<StatueAssets>();
     app.register_ldtk_entity_for_layer::<StatueBundle>("enemies", "statue");
@@ -41,13 +40,6 @@ pub(super) fn plugin(app: &mut App) {
             .in_set(AppSystems::Update)
             .run_if(in_state(Screen::WorldGen)),
     );
-    app.add_systems(
-        Update,
-        update_statue
-            .in_set(AgedSystems)
-            .in_set(AppSystems::Update)
-            .run_if(in_state(Screen::Gameplay)),
-    );
     app.add_systems(
         Update,
         animate_statue
@@ -57,20 +49,8 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-#[derive(Clone, Default, Debug, PartialEq)]
-enum State {
-    #[default]
-    Roaming,
-    Aggro,
-    Attacking,
-}
-
 #[derive(Clone, Default, Component)]
-struct Statue {
-    state: State,
-    dir: bool,
-    dirty: bool,
-}
+struct Statue;
 
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
@@ -107,21 +87,30 @@ struct StatueBundle {
 }
 
 fn init_statue(
-    mut query: Query<(Entity, &mut Transform), Added<Statue>>,
+    mut query: Query<(Entity, &mut Transform, Option<&EntityInstance>), Added<Statue>>,
+    scaling: EnemyScaling,
     mut commands: Commands,
     assets: Res<StatueAssets>,
 ) {
-    for (entity, mut transform) in query.iter_mut() {
+    for (entity, mut transform, instance) in query.iter_mut() {
         let Ok(mut command) = commands.get_entity(entity) else {
             continue;
         };
         transform.translation.z = 2.0;
+        let stats = scaling.stats(
+            entity,
+            EnemyStats::new(140.0, 100.0, 15.0, 500.0, 0.0),
+            instance,
+        );
         let atlas = TextureAtlas {
             layout: assets.atlas_walk.clone(),
             index: 0,
         };
         command.insert((
-            Health::new(140.0),
+            Health::new(stats.health)
+                .with_resistance(DamageKind::Fire, 0.75)
+                .with_resistance(DamageKind::Current, 0.5)
+                .with_resistance(DamageKind::Arcane, -0.25),
             Timed::default(),
             Enemy,
             Sprite {
@@ -133,6 +122,7 @@ fn init_statue(
             YSort::new(ENTITY_LAYER, 64.0),
             RigidBody::Dynamic,
             Collider::capsule(16.0, 32.0),
+            CollisionLayers::new(GameLayer::Enemy, LayerMask::ALL),
             // TnuaAnimatingState::<AnimationState>::default(),
             TnuaController::default(),
             TnuaAvian2dSensorShape(Collider::rectangle(31.0, 0.0)),
@@ -145,115 +135,45 @@ fn init_statue(
             },
             RayCaster::new(Vec2::ZERO, Dir2::X),
         ));
-    }
-}
-
-fn update_statue(
-    mut query: Query<(&GlobalTransform, &mut Statue, &mut TnuaController, Entity), Without<Dead>>,
-    mut player_query: Query<(&GlobalTransform, Entity, &mut Health), With<Player>>,
-    spatial_query: SpatialQuery,
-) {
-    let Ok((player, playerentity, mut health)) = player_query.single_mut() else {
-        return;
-    };
-    let playerpos = player.translation().xy();
-    for (transform, mut statue, mut controller, entity) in query.iter_mut() {
-        let statuepos = transform.translation().xy();
-        let mut sees_obstacle = false;
-        let mut sees_player = false;
-        let mut dir = match statue.dir {
-            true => Vec2::new(1.0, 0.0),
-            false => Vec2::new(-1.0, 0.0),
-        };
-        let filter = SpatialQueryFilter::default().with_excluded_entities([entity, playerentity]);
-        let rayhit =
-            spatial_query.cast_ray(statuepos + dir * 25.0, Dir2::NEG_Y, 50.0, true, &filter);
-        if let Some(rayhit) = rayhit {
-            if rayhit.distance <= 10.0 || rayhit.distance >= 35.0 {
-                sees_obstacle = true;
-            }
-        } else {
-            sees_obstacle = true;
-        }
-        let filter = SpatialQueryFilter::default().with_excluded_entities([entity]);
-        if let Ok(ndir) = Dir2::new(playerpos - statuepos) {
-            let rayhit = spatial_query.cast_ray(statuepos, ndir, 500.0, true, &filter);
-            if let Some(rayhit) = rayhit {
-                sees_player = rayhit.entity == playerentity;
-            }
-        }
-        let mut nextstate = statue.state.clone();
-        // println!("My State is: {:?}", statue.state);
-        match statue.state {
-            State::Roaming => {
-                if sees_obstacle {
-                    statue.dir = !statue.dir;
-                    dir.x = 0.0;
-                }
-                if sees_player {
-                    nextstate = State::Aggro;
-                }
-            }
-            State::Aggro => {
-                statue.dir = (playerpos.x - statuepos.x) > 0.0;
-                let distance = statuepos.distance_squared(playerpos);
-                if distance > (2000.0).powi(2) && !sees_player {
-                    nextstate = State::Roaming;
-                }
-                if sees_obstacle {
-                    dir.x = 0.0;
-                }
-                if distance < (50.0).powi(2) {
-                    nextstate = State::Attacking;
-                    dir.x = 0.0;
-                }
-            }
-            State::Attacking => {
-                statue.dir = (playerpos.x - statuepos.x) > 0.0;
-                let res = spatial_query.shape_intersections(
-                    &Collider::circle(50.0),
-                    statuepos,
-                    0.0,
-                    &SpatialQueryFilter::default(),
-                );
-                if res.contains(&playerentity) {
-                    health.damage(15.0);
-                }
-                dir.x = 0.0;
-            }
-        }
-        if nextstate != statue.state {
-            statue.state = nextstate;
-            statue.dirty = true;
-        }
-        controller.basis(TnuaBuiltinWalk {
-            // The `desired_velocity` determines how the character will move.
-            desired_velocity: Vec3::new(dir.x, 0.0, 0.0) * 100.0,
-            acceleration: Float::INFINITY,
-            // The `float_height` must be greater (even if by little) from the distance between the
-            // character's center and the lowest point of its collider.
-            float_height: 33.0,
-            // `TnuaBuiltinWalk` has many other fields for customizing the movement - but they have
-            // sensible defaults. Refer to the `TnuaBuiltinWalk`'s documentation to learn what they do.
-            ..TnuaBuiltinWalk::default()
-        });
+        command.insert((
+            Brain::facing(Vec2::NEG_X),
+            Perception::new(stats.aggro_range, 2000.0),
+            Locomotion::Walker {
+                speed: stats.speed,
+                float_height: 33.0,
+                jump: 70.0,
+            },
+            Patrol::default(),
+            Chase,
+            Navigator::default(),
+            Melee {
+                trigger: 50.0,
+                reach: 50.0,
+                active: (2, 2),
+                damage: stats.damage,
+                knockback: ATTACK_KNOCKBACK,
+                stun: ATTACK_STUN,
+                dash: None,
+                cooldown: Timer::from_seconds(stats.attack_cooldown, TimerMode::Once),
+            },
+        ));
     }
 }
 
 fn animate_statue(
-    mut query: Query<(&mut Statue, &mut AnimationConfig, &mut Sprite), Without<Dead>>,
+    mut query: Query<
+        (&mut Brain, &mut AnimationConfig),
+        (With<Statue>, Without<Dead>, Without<Stunned>),
+    >,
     assets: Res<StatueAssets>,
 ) {
-    for (mut statue, mut animconf, mut sprite) in query.iter_mut() {
-        if statue.state == State::Attacking && !animconf.is_playing() {
-            statue.state = State::Aggro;
-        }
-        if !statue.dirty {
+    for (mut brain, mut animconf) in query.iter_mut() {
+        if !brain.dirty {
             continue;
         }
-        statue.dirty = false;
-        match statue.state {
-            State::Roaming | State::Aggro => {
+        brain.dirty = false;
+        match brain.state {
+            AiState::Roaming | AiState::Aggro => {
                 animconf.update_sprite(
                     Some(TextureAtlas {
                         layout: assets.atlas_walk.clone(),
@@ -265,7 +185,7 @@ fn animate_statue(
                 animconf.set_looping(true);
                 animconf.play();
             }
-            State::Attacking => {
+            AiState::Attacking => {
                 animconf.update_sprite(
                     Some(TextureAtlas {
                         layout: assets.atlas_attack.clone(),
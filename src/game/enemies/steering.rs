@@ -0,0 +1,146 @@
+//! Steering behaviours for flying enemies. The behaviours of the AI pick a direction with
+//! [`seek`], [`flee`], [`arrive`] or [`wander`], and [`steer`] blends it with [`separation`] from
+//! other flyers and whisker based obstacle avoidance before accelerating towards it, so flyers
+//! glide around walls instead of bouncing off them.
+
+use avian2d::prelude::{LinearVelocity, SpatialQuery, SpatialQueryFilter};
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::game::{
+    age::Dead,
+    enemies::ai::{Brain, Locomotion, Perception},
+    health::Stunned,
+    layers::GameLayer,
+};
+
+/// Angles of the whiskers relative to the heading, with how strongly each one pushes.
+const WHISKERS: [(f32, f32); 5] = [(0.0, 1.0), (0.5, 0.8), (-0.5, 0.8), (1.1, 0.5), (-1.1, 0.5)];
+
+/// How a flyer accelerates and keeps clear of walls and other flyers.
+#[derive(Component, Clone)]
+pub(super) struct Steering {
+    /// How fast the velocity may change in units per second squared.
+    pub max_force: f32,
+    /// Distance kept from other flyers.
+    pub separation: f32,
+    /// Length of the whiskers used to feel for walls.
+    pub whisker: f32,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Self {
+            max_force: 600.0,
+            separation: 120.0,
+            whisker: 120.0,
+        }
+    }
+}
+
+/// Full speed towards `target`.
+pub(super) fn seek(pos: Vec2, target: Vec2) -> Vec2 {
+    (target - pos).normalize_or_zero()
+}
+
+/// Full speed away from `threat`.
+pub(super) fn flee(pos: Vec2, threat: Vec2) -> Vec2 {
+    -seek(pos, threat)
+}
+
+/// Towards `target`, slowing down once closer than `slowing`.
+pub(super) fn arrive(pos: Vec2, target: Vec2, slowing: f32) -> Vec2 {
+    seek(pos, target) * (pos.distance(target) / slowing).min(1.0)
+}
+
+/// Turns `heading` by a random angle of at most `jitter` radians.
+pub(super) fn wander(heading: Vec2, jitter: f32) -> Vec2 {
+    let angle = rand::thread_rng().gen_range(-jitter..=jitter);
+    Vec2::from_angle(angle).rotate(heading.normalize_or(Vec2::X))
+}
+
+/// Pushes away from every neighbour closer than `radius`, harder the closer it is.
+pub(super) fn separation(pos: Vec2, neighbours: impl Iterator<Item = Vec2>, radius: f32) -> Vec2 {
+    neighbours
+        .filter(|other| other.distance(pos) < radius)
+        .map(|other| (pos - other).normalize_or_zero() * (1.0 - other.distance(pos) / radius))
+        .sum()
+}
+
+/// Feels ahead with the [`WHISKERS`] and pushes away from the walls they touch. A wall straight
+/// ahead also pushes sideways towards the more open side so flyers slide out of corners.
+fn avoid(
+    spatial_query: &SpatialQuery,
+    filter: &SpatialQueryFilter,
+    pos: Vec2,
+    heading: Vec2,
+    length: f32,
+) -> Vec2 {
+    let mut push = Vec2::ZERO;
+    let mut clearance = [length; WHISKERS.len()];
+    for (index, (angle, weight)) in WHISKERS.iter().enumerate() {
+        let Ok(dir) = Dir2::new(Vec2::from_angle(*angle).rotate(heading)) else {
+            continue;
+        };
+        if let Some(hit) = spatial_query.cast_ray(pos, dir, length, true, filter) {
+            clearance[index] = hit.distance;
+            push += hit.normal * (1.0 - hit.distance / length) * weight;
+        }
+    }
+    if clearance[0] < length {
+        let side = match clearance[1] + clearance[3] >= clearance[2] + clearance[4] {
+            true => heading.perp(),
+            false => -heading.perp(),
+        };
+        push += side * (1.0 - clearance[0] / length);
+    }
+    push.clamp_length_max(1.5)
+}
+
+/// Accelerates flyers towards the movement their [`Brain`] wants, keeping them apart, clear of
+/// walls and within their hover band.
+pub(super) fn steer(
+    mut query: Query<
+        (
+            &GlobalTransform,
+            &Brain,
+            &Perception,
+            &Locomotion,
+            &Steering,
+            &mut LinearVelocity,
+            Entity,
+        ),
+        (Without<Dead>, Without<Stunned>),
+    >,
+    flyers: Query<(&GlobalTransform, Entity), (With<Steering>, Without<Dead>)>,
+    spatial_query: SpatialQuery,
+    time: Res<Time>,
+) {
+    let filter = SpatialQueryFilter::from_mask(GameLayer::Wall);
+    for (transform, brain, perception, locomotion, steering, mut velocity, entity) in
+        query.iter_mut()
+    {
+        let Locomotion::Flyer { speed, hover } = locomotion else {
+            continue;
+        };
+        let pos = transform.translation().xy();
+        let neighbours = flyers
+            .iter()
+            .filter(|(_, other)| *other != entity)
+            .map(|(other, _)| other.translation().xy());
+
+        let mut desired = brain.movement + separation(pos, neighbours, steering.separation);
+        let heading = velocity.0.try_normalize().unwrap_or(brain.facing);
+        if heading != Vec2::ZERO {
+            desired += avoid(&spatial_query, &filter, pos, heading, steering.whisker);
+        }
+        match perception.floor {
+            Some(floor) if floor <= hover.0 => desired.y += (hover.0 + 50.0 - floor) / 200.0,
+            Some(floor) if floor >= hover.1 => desired.y -= (floor - hover.1) / 200.0,
+            _ => (),
+        }
+        let desired = desired.clamp_length_max(1.0) * *speed;
+        let force = (desired - velocity.0).clamp_length_max(steering.max_force * time.delta_secs());
+        velocity.0 += force;
+    }
+}
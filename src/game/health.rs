@@ -1,17 +1,134 @@
 use std::time::Duration;
 
-use bevy::{ecs::system::command, prelude::*};
+use avian2d::prelude::{ComputedMass, ExternalImpulse, LinearVelocity, RigidBody};
+use bevy::{ecs::system::command, platform::collections::HashMap, prelude::*};
+use bevy_tnua::prelude::{TnuaBuiltinWalk, TnuaController};
 
 use crate::{AgedSystems, AppSystems, PausableSystems, game::age::Dead, screens::Screen};
 
+/// Damage per second dealt by [`Status::Burning`].
+const BURN_DPS: f32 = 8.0;
+/// Seconds between two hits of [`Status::Burning`].
+const BURN_TICK: f32 = 1.0;
+
 pub(super) fn plugin(app: &mut App) {
+    app.add_event::<DamageEvent>();
+    app.add_event::<DamageTaken>();
+    app.add_event::<Died>();
     app.add_systems(
         Update,
-        (tick_timer, hurt_effect)
+        (apply_damage, tick_timer, hurt_effect)
+            .chain()
             .in_set(AppSystems::Update)
             .in_set(AgedSystems)
             .run_if(in_state(Screen::Gameplay)),
     );
+    app.add_systems(PostUpdate, slow_movement.run_if(in_state(Screen::Gameplay)));
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum DamageKind {
+    #[default]
+    Physical,
+    Arcane,
+    Fire,
+    Current,
+    Time,
+}
+
+impl DamageKind {
+    /// The status a hit of this kind inflicts and for how many seconds.
+    fn status(&self) -> Option<(Status, f32)> {
+        match self {
+            DamageKind::Fire => Some((Status::Burning, 3.0)),
+            DamageKind::Current => Some((Status::Shocked, 0.6)),
+            DamageKind::Time => Some((Status::Slowed, 2.0)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+    /// Takes damage over time.
+    Burning,
+    /// Stunned for a short moment.
+    Shocked,
+    /// Moves at half speed.
+    Slowed,
+    /// Stuck in stopped time.
+    Frozen,
+    /// Reeling from a hit and unable to act while knocked back.
+    Staggered,
+}
+
+impl Status {
+    pub fn stuns(&self) -> bool {
+        matches!(self, Status::Shocked | Status::Frozen | Status::Staggered)
+    }
+
+    fn tint(&self) -> Color {
+        match self {
+            Status::Burning => Color::linear_rgb(1.0, 0.5, 0.2),
+            Status::Shocked => Color::linear_rgb(1.0, 1.0, 0.3),
+            Status::Slowed => Color::linear_rgb(0.5, 0.6, 1.0),
+            Status::Frozen => Color::linear_rgb(0.6, 0.9, 1.0),
+            Status::Staggered => Color::linear_rgb(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+struct ActiveStatus {
+    status: Status,
+    timer: Timer,
+}
+
+/// Present while the entity is stunned. Enemy AI does not run for stunned entities.
+#[derive(Component)]
+pub struct Stunned;
+
+/// Asks to damage `target`. Every source of damage goes through this event, so invulnerability
+/// frames, resistances, shields and knockback behave the same for all of them.
+#[derive(Event, Clone, Copy)]
+pub struct DamageEvent {
+    pub target: Entity,
+    /// The entity responsible for the damage, used for kill attribution.
+    pub source: Option<Entity>,
+    pub amount: f32,
+    pub kind: DamageKind,
+    /// Change in velocity of the target if the hit goes through.
+    pub knockback: Vec2,
+    /// Seconds the target is [`Status::Staggered`] for.
+    pub stun: f32,
+    /// Whether the hit inflicts the status of its kind. Off for the hits of a status itself, so
+    /// burning does not keep itself going.
+    pub inflicts_status: bool,
+    /// Kills the target outright, through invulnerability, shields and extra lives, ignoring
+    /// `amount`. Used by hazards like spikes that the target would otherwise stay stuck in.
+    pub lethal: bool,
+}
+
+/// Sent for every hit that went through, with the damage that was actually dealt.
+#[derive(Event)]
+pub struct DamageTaken {
+    pub entity: Entity,
+    pub source: Option<Entity>,
+    pub amount: f32,
+    pub kind: DamageKind,
+}
+
+/// Sent when an entity runs out of health and lives.
+#[derive(Event)]
+pub struct Died {
+    pub entity: Entity,
+    /// Source of the last hit the entity took.
+    pub killer: Option<Entity>,
+}
+
+/// Absorbs damage before it reaches [`Health`].
+#[derive(Component)]
+pub struct Shield {
+    pub amount: f32,
 }
 
 #[derive(Component)]
@@ -19,6 +136,18 @@ pub struct Health {
     pub(crate) health: f32,
     max_health: f32,
     hurt_time: Timer,
+    /// Runs after a heal to flash the sprite green.
+    heal_time: Timer,
+    /// No damage is taken until this runs out, without the hurt flash of `hurt_time`.
+    invulnerable: Timer,
+    last_hit_by: Option<Entity>,
+    /// Runs while burning, hurting the entity every time it finishes.
+    burn_tick: Timer,
+    /// How often the entity survives a killing blow.
+    pub lives: u32,
+    /// Fraction of the damage of a kind that is ignored. Negative values are weaknesses.
+    resistances: HashMap<DamageKind, f32>,
+    statuses: Vec<ActiveStatus>,
 }
 
 impl Health {
@@ -26,47 +155,386 @@ impl Health {
         Self {
             health: health,
             hurt_time: Timer::new(Duration::from_secs_f32(0.0), TimerMode::Once),
+            heal_time: Timer::new(Duration::from_secs_f32(0.0), TimerMode::Once),
+            invulnerable: Timer::new(Duration::from_secs_f32(0.0), TimerMode::Once),
             max_health: health,
+            last_hit_by: None,
+            burn_tick: Timer::from_seconds(BURN_TICK, TimerMode::Repeating),
+            lives: 0,
+            resistances: Default::default(),
+            statuses: Default::default(),
         }
     }
 
+    pub fn with_resistance(mut self, kind: DamageKind, resistance: f32) -> Self {
+        self.resistances.insert(kind, resistance);
+        self
+    }
+
+    pub fn resistance(&self, kind: DamageKind) -> f32 {
+        self.resistances.get(&kind).copied().unwrap_or_default()
+    }
+
     pub fn get_percent(&self) -> f32 {
         self.health / self.max_health
     }
 
-    pub fn damage(&mut self, damage: f32) {
-        if !self.hurt_time.finished() {
-            return;
+    /// Applies `status` for `secs` seconds, refreshing it if it is already active.
+    pub fn apply_status(&mut self, status: Status, secs: f32) {
+        if let Some(active) = self.statuses.iter_mut().find(|a| a.status == status) {
+            if active.timer.remaining_secs() < secs {
+                active.timer = Timer::from_seconds(secs, TimerMode::Once);
+            }
+        } else {
+            self.statuses.push(ActiveStatus {
+                status,
+                timer: Timer::from_seconds(secs, TimerMode::Once),
+            });
+        }
+    }
+
+    /// Ignores all damage for `secs` seconds, keeping a longer invulnerability that is running.
+    pub fn make_invulnerable(&mut self, secs: f32) {
+        if self.invulnerable.remaining_secs() < secs {
+            self.invulnerable = Timer::from_seconds(secs, TimerMode::Once);
         }
-        self.health -= damage;
-        self.hurt_time = Timer::new(Duration::from_secs_f32(0.5), TimerMode::Once);
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerable.finished()
+    }
+
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses.iter().any(|a| a.status == status)
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.statuses.iter().any(|a| a.status.stuns())
+    }
+
+    /// Whether the entity only lost control because of a hit, so it should keep its momentum.
+    pub fn is_staggered(&self) -> bool {
+        self.has_status(Status::Staggered)
+            && !self.has_status(Status::Shocked)
+            && !self.has_status(Status::Frozen)
+    }
+
+    /// Multiplier for how fast the entity can currently move.
+    pub fn speed_factor(&self) -> f32 {
+        if self.is_stunned() {
+            0.0
+        } else if self.has_status(Status::Slowed) {
+            0.5
+        } else {
+            1.0
+        }
+    }
+
+    pub fn max_health(&self) -> f32 {
+        self.max_health
+    }
+
+    /// Heals up to `amount` without going over the maximum and returns how much was healed.
+    pub fn heal(&mut self, amount: f32) -> f32 {
+        let healed = amount.min(self.max_health - self.health).max(0.0);
+        if healed > 0.0 {
+            self.health += healed;
+            self.heal_time = Timer::new(Duration::from_secs_f32(0.4), TimerMode::Once);
+        }
+        healed
+    }
+
+    /// Raises the maximum health and heals by the same amount.
+    pub fn raise_max_health(&mut self, amount: f32) {
+        self.max_health += amount;
+        self.heal(amount);
+    }
+}
+
+fn apply_damage(
+    mut events: EventReader<DamageEvent>,
+    mut targets: Query<
+        (
+            &mut Health,
+            Option<&mut Shield>,
+            Option<(&RigidBody, &ComputedMass, &mut LinearVelocity)>,
+        ),
+        Without<Dead>,
+    >,
+    mut damage_taken: EventWriter<DamageTaken>,
+    mut died: EventWriter<Died>,
+    mut commands: Commands,
+) {
+    for event in events.read() {
+        let Ok((mut health, shield, body)) = targets.get_mut(event.target) else {
+            continue;
+        };
+        // Already killed by an earlier hit this frame.
+        if health.health <= 0.0 {
+            continue;
+        }
+        let mut amount = if event.lethal {
+            health.health
+        } else {
+            if !health.hurt_time.finished() || health.is_invulnerable() {
+                continue;
+            }
+            let resistance = health.resistance(event.kind);
+            if resistance >= 1.0 {
+                continue;
+            }
+            event.amount * (1.0 - resistance)
+        };
+        if let Some(mut shield) = shield.filter(|_| !event.lethal) {
+            let absorbed = amount.min(shield.amount);
+            shield.amount -= absorbed;
+            amount -= absorbed;
+        }
+        health.health -= amount;
+        health.last_hit_by = event.source;
+        health.hurt_time = Timer::new(Duration::from_secs_f32(0.5), TimerMode::Once);
+        if let Some((status, secs)) = event.kind.status().filter(|_| event.inflicts_status) {
+            health.apply_status(status, secs);
+        }
+        if event.stun > 0.0 {
+            health.apply_status(Status::Staggered, event.stun);
+        }
+        match body {
+            Some((RigidBody::Dynamic, mass, _)) => {
+                commands
+                    .entity(event.target)
+                    .insert(ExternalImpulse::new(event.knockback * mass.value()));
+            }
+            Some((_, _, mut velocity)) => velocity.0 += event.knockback,
+            None => (),
+        }
+        damage_taken.write(DamageTaken {
+            entity: event.target,
+            source: event.source,
+            amount,
+            kind: event.kind,
+        });
+        if health.health > 0.0 {
+            continue;
+        }
+        if health.lives > 0 && !event.lethal {
+            health.lives -= 1;
+            health.health = health.max_health / 2.0;
+            continue;
+        }
+        died.write(Died {
+            entity: event.target,
+            killer: health.last_hit_by,
+        });
+        commands.entity(event.target).insert(Dead);
     }
 }
 
 fn tick_timer(
-    mut query: Query<(&mut Health, Entity), Without<Dead>>,
+    mut query: Query<(&mut Health, Entity, Has<Stunned>), Without<Dead>>,
     time: Res<Time>,
+    mut damage: EventWriter<DamageEvent>,
     mut commands: Commands,
 ) {
-    for (mut health, entity) in query.iter_mut() {
+    for (mut health, entity, stunned) in query.iter_mut() {
         health.hurt_time.tick(time.delta());
-        if health.health <= 0.0 {
-            commands.entity(entity).insert(Dead);
+        health.heal_time.tick(time.delta());
+        health.invulnerable.tick(time.delta());
+        for active in health.statuses.iter_mut() {
+            active.timer.tick(time.delta());
+        }
+        // Burning hurts in whole ticks through the damage pipeline, so shields and invulnerability
+        // apply and every tick counts as one hit. The last tick lands as the status runs out.
+        if health.has_status(Status::Burning) {
+            health.burn_tick.tick(time.delta());
+            for _ in 0..health.burn_tick.times_finished_this_tick() {
+                damage.write(DamageEvent {
+                    target: entity,
+                    source: health.last_hit_by,
+                    amount: BURN_DPS * BURN_TICK,
+                    kind: DamageKind::Fire,
+                    knockback: Vec2::ZERO,
+                    stun: 0.0,
+                    inflicts_status: false,
+                    lethal: false,
+                });
+            }
+        } else {
+            health.burn_tick.reset();
+        }
+        health.statuses.retain(|active| !active.timer.finished());
+        if health.is_stunned() != stunned {
+            if stunned {
+                commands.entity(entity).remove::<Stunned>();
+            } else {
+                commands.entity(entity).insert(Stunned);
+            }
         }
     }
 }
 
 fn hurt_effect(mut query: Query<(&Health, &mut Sprite)>) {
     for (health, mut sprite) in query.iter_mut() {
+        let tint = health
+            .statuses
+            .last()
+            .map(|active| active.status.tint())
+            .unwrap_or(Color::linear_rgb(1.0, 1.0, 1.0));
+        let tint = if health.is_invulnerable() {
+            tint.with_alpha(0.5)
+        } else {
+            tint
+        };
         if !health.hurt_time.finished() {
             let t = (health.hurt_time.elapsed_secs() * 10.0).floor() as i32;
             if t % 2 == 0 {
                 sprite.color = Color::linear_rgb(1.0, 0.0, 0.0);
             } else {
-                sprite.color = Color::linear_rgb(1.0, 1.0, 1.0);
+                sprite.color = tint;
+            }
+        } else if !health.heal_time.finished() {
+            let t = (health.heal_time.elapsed_secs() * 10.0).floor() as i32;
+            if t % 2 == 0 {
+                sprite.color = Color::linear_rgb(0.3, 1.0, 0.3);
+            } else {
+                sprite.color = tint;
             }
         }else{
-            sprite.color = Color::linear_rgb(1.0, 1.0, 1.0);
+            sprite.color = tint;
         }
     }
 }
+
+/// Scales down the movement the AI and inputs asked for this frame for slowed or stunned
+/// entities. Staggered entities lose control instead, so their knockback carries them.
+fn slow_movement(
+    mut query: Query<
+        (
+            &Health,
+            &RigidBody,
+            &mut LinearVelocity,
+            Option<&mut TnuaController>,
+        ),
+        Without<Dead>,
+    >,
+    time: Res<Time>,
+) {
+    for (health, body, mut velocity, controller) in query.iter_mut() {
+        if health.is_staggered() {
+            if let Some(mut controller) = controller {
+                let walk = controller
+                    .concrete_basis::<TnuaBuiltinWalk>()
+                    .map(|(walk, _)| walk.clone());
+                if let Some(walk) = walk {
+                    controller.basis(TnuaBuiltinWalk {
+                        acceleration: 0.0,
+                        air_acceleration: 0.0,
+                        ..walk
+                    });
+                }
+            } else if *body == RigidBody::Kinematic {
+                velocity.0 *= (-8.0 * time.delta_secs()).exp();
+            }
+            continue;
+        }
+        let factor = health.speed_factor();
+        if factor == 1.0 {
+            continue;
+        }
+        if let Some(mut controller) = controller {
+            let walk = controller
+                .concrete_basis::<TnuaBuiltinWalk>()
+                .map(|(walk, _)| walk.clone());
+            if let Some(walk) = walk {
+                controller.basis(TnuaBuiltinWalk {
+                    desired_velocity: walk.desired_velocity * factor,
+                    ..walk
+                });
+            }
+        } else if *body == RigidBody::Kinematic {
+            velocity.0 *= factor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<DamageEvent>();
+        app.add_event::<DamageTaken>();
+        app.add_event::<Died>();
+        app.init_resource::<Time>();
+        app.add_systems(Update, (apply_damage, tick_timer).chain());
+        app
+    }
+
+    /// Spawns `health` and runs a frame, so the timers of the fresh [`Health`] have ticked once.
+    fn spawn(app: &mut App, health: impl Bundle) -> Entity {
+        let entity = app.world_mut().spawn(health).id();
+        app.update();
+        entity
+    }
+
+    fn hit(app: &mut App, target: Entity, lethal: bool) {
+        app.world_mut().send_event(DamageEvent {
+            target,
+            source: None,
+            amount: 10.0,
+            kind: DamageKind::Physical,
+            knockback: Vec2::ZERO,
+            stun: 0.0,
+            inflicts_status: true,
+            lethal,
+        });
+        app.update();
+    }
+
+    fn died(app: &App) -> usize {
+        app.world()
+            .resource::<Events<Died>>()
+            .iter_current_update_events()
+            .count()
+    }
+
+    /// A player in the middle of a dash, see `dash::update_dash`.
+    fn dashing_player(app: &mut App) -> Entity {
+        let mut health = Health::new(100.0);
+        health.lives = 1;
+        health.make_invulnerable(0.3);
+        spawn(app, (health, Shield { amount: 50.0 }))
+    }
+
+    #[test]
+    fn dash_invulnerability_blocks_hits() {
+        let mut app = app();
+        let player = dashing_player(&mut app);
+        hit(&mut app, player, false);
+        assert_eq!(app.world().get::<Health>(player).unwrap().health, 100.0);
+        assert_eq!(died(&app), 0);
+    }
+
+    #[test]
+    fn dash_onto_spikes_still_kills() {
+        let mut app = app();
+        let player = dashing_player(&mut app);
+        hit(&mut app, player, true);
+        assert!(app.world().entity(player).contains::<Dead>());
+        assert_eq!(died(&app), 1);
+    }
+
+    #[test]
+    fn killing_blow_takes_a_life() {
+        let mut app = app();
+        let mut health = Health::new(5.0);
+        health.lives = 1;
+        let player = spawn(&mut app, health);
+        hit(&mut app, player, false);
+        let health = app.world().get::<Health>(player).unwrap();
+        assert_eq!((health.lives, health.health), (0, 2.5));
+        assert!(!app.world().entity(player).contains::<Dead>());
+        assert_eq!(died(&app), 0);
+    }
+}
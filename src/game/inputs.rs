@@ -1,24 +1,28 @@
-use crate::game::age::{Age, Timed};
-use crate::game::animate::{AnimationConfig, Directional};
-use crate::game::enemies::ghost::GhostAssets;
+use crate::game::age::Age;
+use crate::game::health::Stunned;
+use crate::game::melee::Strike;
+use crate::game::platforms::DropThrough;
 use crate::game::player::Book;
-use crate::game::projectile::ProjectileTarget;
+use crate::game::spells::{CastSpell, SpellBook};
+use crate::game::wall::{Climber, SLIDE_SPEED, WallAbilities, WallContact};
+use crate::game::world::collider::Material;
 use crate::{
     AgedSystems, AppSystems, PausableSystems,
-    game::{
-        age::Aged,
-        player::{Player, SpellCap},
-        projectile::Projectile,
-    },
+    game::{age::Aged, player::Player},
     screens::Screen,
 };
+use avian2d::prelude::{GravityScale, LinearVelocity};
 use bevy::math::VectorSpace;
 use bevy::prelude::Vec2;
 use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_enhanced_input::prelude::*;
 use bevy_light_2d::light::PointLight2d;
-use bevy_tnua::control_helpers::TnuaSimpleAirActionsCounter;
-use bevy_tnua::{builtins::TnuaBuiltinDash, math::Float, prelude::*};
+use bevy_tnua::control_helpers::{
+    TnuaSimpleAirActionsCounter, TnuaSimpleFallThroughPlatformsHelper,
+};
+use bevy_tnua::{
+    TnuaGhostSensor, TnuaProximitySensor, builtins::TnuaBuiltinDash, math::Float, prelude::*,
+};
 
 #[derive(InputContext)]
 struct DefaultContext;
@@ -27,6 +31,14 @@ struct DefaultContext;
 #[input_action(output = bool)]
 struct Attack;
 
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+struct Melee;
+
+#[derive(Debug, InputAction)]
+#[input_action(output = bool)]
+struct CycleSpell;
+
 #[derive(Debug, InputAction)]
 #[input_action(output = bool)]
 struct Turnback;
@@ -60,14 +72,15 @@ pub(super) fn plugin(app: &mut App) {
     );
     app.add_systems(
         Update,
-        shoot
+        (shoot, strike, cycle_spell)
             .in_set(AppSystems::Update)
             .in_set(AgedSystems)
             .run_if(in_state(Screen::Gameplay)),
     );
     app.add_systems(
         Update,
-        movement
+        (movement, wall_movement)
+            .chain()
             .in_set(AppSystems::Update)
             .in_set(AgedSystems)
             .run_if(in_state(Screen::Gameplay)),
@@ -87,6 +100,12 @@ fn init_inputs(mut commands: Commands) {
     actions
         .bind::<Attack>()
         .to((MouseButton::Left, GamepadButton::RightThumb));
+    actions
+        .bind::<Melee>()
+        .to((MouseButton::Right, KeyCode::KeyF, GamepadButton::West));
+    actions
+        .bind::<CycleSpell>()
+        .to((KeyCode::KeyQ, GamepadButton::North));
     actions
         .bind::<Turnback>()
         .to((KeyCode::KeyR, GamepadButton::LeftTrigger2));
@@ -116,14 +135,9 @@ fn shoot(
     actions: Single<&Actions<DefaultContext>>,
     q_window: Query<&Window, With<PrimaryWindow>>,
     q_camera: Query<(&Camera, &GlobalTransform)>,
-    mut spells: Single<(&mut SpellCap, Entity), With<Player>>,
     book: Single<(&GlobalTransform), With<Book>>,
-    mut command: Commands,
-    assets: Res<GhostAssets>,
-    time: Res<Time>,
+    mut casts: EventWriter<CastSpell>,
 ) {
-    let (mut spells, entity) = spells.into_inner();
-    spells.timer.tick(time.delta());
     let (transform) = book.into_inner();
     let Ok((camera, camera_transform)) = q_camera.single() else {
         return;
@@ -140,132 +154,56 @@ fn shoot(
     {
         let pos = world_position;
         let shootpos = transform.translation();
-        if actions.state::<Attack>().unwrap() == ActionState::Fired && spells.timer.finished() {
-            spells.timer =
-                Timer::from_seconds((1.0 / (1.0 + spells.speed / 10.0)) as f32, TimerMode::Once);
-            let dir = (pos - shootpos.xy()).normalize();
-            let atlas = TextureAtlas {
-                layout: assets.atlas_proj.clone(),
-                index: 0,
-            };
-            let size = Vec2::splat(60.0);
-            let mut spellcommand = command.spawn((
-                StateScoped(Screen::Gameplay),
-                Transform::from_translation(shootpos),
-                Timed::default(),
-                Directional {
-                    flipdir: true,
-                    ..Default::default()
-                },
-                Projectile {
-                    target: ProjectileTarget::Enemies,
-                    size: size / 2.0,
-                    dmg: (40.0 + spells.strength * 20.0) as f32,
-                    dir: dir * 300.0 + (spells.speed * 50.0) as f32, //
-                },
-                Sprite {
-                    image: assets.sprite_proj.clone(),
-                    texture_atlas: Some(atlas.clone()),
-                    custom_size: Some(size),
-                    ..Default::default()
-                },
-                AnimationConfig::new(
-                    0,
-                    8,
-                    4,
-                    true,
-                    true,
-                    Some(atlas.clone()),
-                    assets.sprite_proj.clone(),
-                ),
-            ));
-            for item in &spells.items {
-                match item.name.as_str() {
-                    "Book of Fire" => {
-                        let atlas = TextureAtlas {
-                            layout: assets.atlas_proj_fire.clone(),
-                            index: 0,
-                        };
-                        spellcommand.insert((
-                            Sprite {
-                                image: assets.sprite_proj_fire.clone(),
-                                texture_atlas: Some(atlas.clone()),
-                                custom_size: Some(size),
-                                ..Default::default()
-                            },
-                            AnimationConfig::new(
-                                0,
-                                8,
-                                4,
-                                true,
-                                true,
-                                Some(atlas.clone()),
-                                assets.sprite_proj_fire.clone(),
-                            ),
-                        ));
-                    }
-                    "Book of Current" => {
-                        let atlas = TextureAtlas {
-                            layout: assets.atlas_proj_electro.clone(),
-                            index: 0,
-                        };
-                        spellcommand.insert((
-                            Sprite {
-                                image: assets.sprite_proj_electro.clone(),
-                                texture_atlas: Some(atlas.clone()),
-                                custom_size: Some(size),
-                                ..Default::default()
-                            },
-                            AnimationConfig::new(
-                                3,
-                                5,
-                                4,
-                                true,
-                                true,
-                                Some(atlas.clone()),
-                                assets.sprite_proj_electro.clone(),
-                            ),
-                        ));
-                    }
-                    "Duplex" => {
-                        // command.spawn((
-                        //     Transform::from_translation(transform.translation() + Vec3::Y * 30.0),
-                        //     Timed::default(),
-                        //     Directional {
-                        //         flipdir: true,
-                        //         ..Default::default()
-                        //     },
-                        //     Projectile {
-                        //         owner: entity,
-                        //         target: None,
-                        //         size: size / 2.0,
-                        //         dmg: (spells.strength * 2.0) as f32,
-                        //         dir: dir * 200.0 + (spells.speed * 50.0) as f32,
-                        //     },
-                        //     Sprite {
-                        //         image: assets.sprite_proj.clone(),
-                        //         texture_atlas: Some(atlas.clone()),
-                        //         custom_size: Some(size),
-                        //         ..Default::default()
-                        //     },
-                        //     AnimationConfig::new(
-                        //         0,
-                        //         8,
-                        //         4,
-                        //         true,
-                        //         true,
-                        //         Some(atlas.clone()),
-                        //         assets.sprite_proj.clone(),
-                        //     ),
-                        // ));
-                    }
-                    _ => (),
-                }
-            }
+        if actions.state::<Attack>().unwrap() == ActionState::Fired {
+            casts.write(CastSpell {
+                origin: shootpos,
+                dir: (pos - shootpos.xy()).normalize(),
+            });
         }
     }
 }
 
+fn strike(
+    actions: Single<&Actions<DefaultContext>>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    player: Single<(&GlobalTransform, &Sprite), With<Player>>,
+    mut strikes: EventWriter<Strike>,
+    mut held: Local<bool>,
+) {
+    let pressed = actions.state::<Melee>().unwrap() == ActionState::Fired;
+    let started = pressed && !*held;
+    *held = pressed;
+    if !started {
+        return;
+    }
+    let (transform, sprite) = player.into_inner();
+    // Without a cursor the strike goes the way the player is looking, the sprite faces left.
+    let facing = if sprite.flip_x { Vec2::X } else { Vec2::NEG_X };
+    let dir = q_window
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .zip(q_camera.single().ok())
+        .and_then(|(cursor, (camera, camera_transform))| {
+            camera.viewport_to_world_2d(camera_transform, cursor).ok()
+        })
+        .map_or(facing, |pos| pos - transform.translation().xy());
+    strikes.write(Strike { dir });
+}
+
+fn cycle_spell(
+    actions: Single<&Actions<DefaultContext>>,
+    mut book: Single<&mut SpellBook, With<Player>>,
+    mut held: Local<bool>,
+) {
+    let pressed = actions.state::<CycleSpell>().unwrap() == ActionState::Fired;
+    if pressed && !*held {
+        book.cycle();
+    }
+    *held = pressed;
+}
+
 fn movement(
     actions: Single<&Actions<DefaultContext>>,
     mut query: Query<(
@@ -273,10 +211,29 @@ fn movement(
         &mut Aged,
         &mut Player,
         &mut TnuaSimpleAirActionsCounter,
+        &Climber,
+        Has<Stunned>,
+        (
+            &mut TnuaProximitySensor,
+            &TnuaGhostSensor,
+            &mut TnuaSimpleFallThroughPlatformsHelper,
+            Has<DropThrough>,
+            Entity,
+        ),
     )>,
+    materials: Query<&Material>,
     time: Res<Time>,
+    mut commands: Commands,
 ) {
-    let Ok((mut controller, mut aged, mut player, mut air_actions_counter)) = query.single_mut()
+    let Ok((
+        mut controller,
+        mut aged,
+        mut player,
+        mut air_actions_counter,
+        climber,
+        stunned,
+        (mut sensor, ghost_sensor, mut fall_through_helper, dropping, entity),
+    )) = query.single_mut()
     else {
         return;
     };
@@ -286,11 +243,20 @@ fn movement(
     let actions = actions.into_inner();
 
     let direction = actions.value::<Move>().unwrap();
+    // A wall jump pushes the player away from the wall for a moment.
+    let walk = climber.kick_direction().unwrap_or(direction.x);
+    let ground = controller
+        .concrete_basis::<TnuaBuiltinWalk>()
+        .and_then(|(_, state)| state.standing_on_entity())
+        .and_then(|entity| materials.get(entity).ok())
+        .copied()
+        .unwrap_or_default();
 
     controller.basis(TnuaBuiltinWalk {
         // The `desired_velocity` determines how the character will move.
-        desired_velocity: Vec3::new(direction.x, 0.0, 0.0) * 250.0,
-        acceleration: Float::INFINITY,
+        desired_velocity: Vec3::new(walk, 0.0, 0.0) * 250.0,
+        // Slippery ground like ice takes a while to get going on.
+        acceleration: ground.surface().acceleration,
         // The `float_height` must be greater (even if by little) from the distance between the
         // character's center and the lowest point of its collider.
         float_height: 33.0,
@@ -299,6 +265,21 @@ fn movement(
         // sensible defaults. Refer to the `TnuaBuiltinWalk`'s documentation to learn what they do.
         ..TnuaBuiltinWalk::default()
     });
+    // Down and jump drops through one-way platforms instead of jumping.
+    let drop =
+        !stunned && direction.y < 0.0 && actions.state::<Jump>().unwrap() == ActionState::Fired;
+    let mut fall_through = fall_through_helper.with(&mut sensor, ghost_sensor, 1.0);
+    if drop || dropping {
+        if fall_through.try_falling(drop) && drop && !dropping {
+            commands.entity(entity).insert(DropThrough::new(0.3));
+        }
+    } else {
+        fall_through.dont_fall();
+    }
+    // While stunned the walk basis is taken over by the hit and no actions can be started.
+    if stunned {
+        return;
+    }
 
     if actions.state::<Dash>().unwrap() == ActionState::Fired
         && direction.length_squared() > 0.0
@@ -349,11 +330,85 @@ fn movement(
         });
     }
 
-    if actions.state::<Jump>().unwrap() == ActionState::Fired || direction.y > 0.0 {
+    if !drop && (actions.state::<Jump>().unwrap() == ActionState::Fired || direction.y > 0.0) {
+        controller.action(TnuaBuiltinJump {
+            height: 90.0,
+            ..TnuaBuiltinJump::default()
+        });
+    }
+}
+
+/// Wall slides, wall jumps and ledge grabs, overriding the jump fed by [`movement`] when used.
+fn wall_movement(
+    actions: Single<&Actions<DefaultContext>>,
+    player: Single<
+        (
+            &mut TnuaController,
+            &mut LinearVelocity,
+            &mut GravityScale,
+            &mut Climber,
+            &WallContact,
+            &Aged,
+            Has<Stunned>,
+        ),
+        With<Player>,
+    >,
+    time: Res<Time>,
+    mut jump_held: Local<bool>,
+) {
+    let (mut controller, mut velocity, mut gravity, mut climber, contact, aged, stunned) =
+        player.into_inner();
+    let actions = actions.into_inner();
+    let direction = actions.value::<Move>().unwrap();
+    let jump = actions.state::<Jump>().unwrap() == ActionState::Fired || direction.y > 0.0;
+    let jumped = jump && !*jump_held;
+    *jump_held = jump;
+    climber.tick(time.delta());
+
+    let airborne = controller
+        .concrete_basis::<TnuaBuiltinWalk>()
+        .is_some_and(|(_, state)| state.standing_on_entity().is_none());
+    let abilities = WallAbilities::of(aged.to_age());
+    let Some(side) = contact.side.filter(|_| airborne && !stunned) else {
+        climber.hanging = false;
+        gravity.0 = 1.0;
+        return;
+    };
+    let pushing = direction.x * side > 0.0;
+
+    if abilities.grab && contact.ledge.is_some() && pushing && velocity.y <= 0.0 {
+        climber.hanging = true;
+    }
+    if climber.hanging {
+        climber.hanging = false;
+        gravity.0 = 1.0;
+        if jumped {
+            // Pull up over the ledge.
+            climber.kick(side, 0.25);
+            controller.action(TnuaBuiltinJump {
+                height: 70.0,
+                allow_in_air: true,
+                ..TnuaBuiltinJump::default()
+            });
+        } else if direction.y >= 0.0 && direction.x * side >= 0.0 {
+            climber.hanging = true;
+            gravity.0 = 0.0;
+            velocity.0 = Vec2::ZERO;
+        }
+        return;
+    }
+
+    if abilities.jump && jumped {
+        climber.kick(-side, 0.2);
         controller.action(TnuaBuiltinJump {
             height: 90.0,
+            allow_in_air: true,
             ..TnuaBuiltinJump::default()
         });
+        return;
+    }
+    if abilities.slide && pushing && velocity.y < -SLIDE_SPEED {
+        velocity.y = -SLIDE_SPEED;
     }
 }
 
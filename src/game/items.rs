@@ -0,0 +1,176 @@
+//! Item definitions loaded from `assets/items.ron` and the loot table built on top of them.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use rand::{Rng, seq::SliceRandom};
+use serde::Deserialize;
+
+use crate::{asset_tracking::LoadResource, game::effects::ItemEffect};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_asset_loader(ItemDefsLoader);
+    app.load_resource_from_path::<ItemDefs>("items.ron");
+}
+
+/// Index of an [`ItemDef`] inside [`ItemDefs`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Reflect)]
+pub struct ItemId(usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Reflect)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Legendary,
+}
+
+impl Rarity {
+    /// Relative chance of an item of this rarity being picked by [`ItemDefs::roll`].
+    fn weight(&self) -> u32 {
+        match self {
+            Rarity::Common => 10,
+            Rarity::Rare => 5,
+            Rarity::Legendary => 2,
+        }
+    }
+}
+
+/// Marks the element of an item, which unlocks the matching spell.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Reflect)]
+pub enum ItemTag {
+    Fire,
+    Current,
+    Time,
+}
+
+#[derive(Clone, Debug, Reflect)]
+pub struct ItemDef {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub icon: Handle<Image>,
+    pub rarity: Rarity,
+    /// Unique items drop at most once, everything else can stack.
+    pub unique: bool,
+    /// Only one item out of a group can be owned at a time.
+    pub group: Option<String>,
+    pub strength: f64,
+    pub speed: f64,
+    pub tags: Vec<ItemTag>,
+    pub effects: Vec<ItemEffect>,
+}
+
+impl ItemDef {
+    pub fn has_tag(&self, tag: ItemTag) -> bool {
+        self.tags.contains(&tag)
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct ItemDefs {
+    items: Vec<ItemDef>,
+}
+
+impl ItemDefs {
+    pub fn get(&self, id: ItemId) -> &ItemDef {
+        &self.items[id.0]
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = ItemId> {
+        (0..self.items.len()).map(ItemId)
+    }
+
+    /// Picks a random item that may still drop for a player owning `owned`.
+    pub fn roll(&self, owned: &[ItemId], rng: &mut impl Rng) -> Option<ItemId> {
+        let candidates: Vec<ItemId> = self.ids().filter(|id| self.can_drop(*id, owned)).collect();
+        candidates
+            .choose_weighted(rng, |id| self.get(*id).rarity.weight())
+            .ok()
+            .copied()
+    }
+
+    fn can_drop(&self, id: ItemId, owned: &[ItemId]) -> bool {
+        let item = self.get(id);
+        if item.unique && owned.contains(&id) {
+            return false;
+        }
+        match &item.group {
+            Some(group) => !owned
+                .iter()
+                .any(|other| *other != id && self.get(*other).group.as_ref() == Some(group)),
+            None => true,
+        }
+    }
+}
+
+/// The on-disk layout of a single entry in `items.ron`.
+#[derive(Deserialize)]
+struct ItemDefRon {
+    key: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    icon: String,
+    rarity: Rarity,
+    #[serde(default)]
+    unique: bool,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    strength: f64,
+    #[serde(default)]
+    speed: f64,
+    #[serde(default)]
+    tags: Vec<ItemTag>,
+    #[serde(default)]
+    effects: Vec<ItemEffect>,
+}
+
+#[derive(Deserialize)]
+struct ItemDefsRon {
+    items: Vec<ItemDefRon>,
+}
+
+#[derive(Default)]
+struct ItemDefsLoader;
+
+impl AssetLoader for ItemDefsLoader {
+    type Asset = ItemDefs;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<ItemDefs, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let raw: ItemDefsRon = ron::de::from_bytes(&bytes)?;
+        let items = raw
+            .items
+            .into_iter()
+            .map(|item| ItemDef {
+                icon: load_context.load(item.icon),
+                key: item.key,
+                name: item.name,
+                description: item.description,
+                rarity: item.rarity,
+                unique: item.unique,
+                group: item.group,
+                strength: item.strength,
+                speed: item.speed,
+                tags: item.tags,
+                effects: item.effects,
+            })
+            .collect();
+        Ok(ItemDefs { items })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
@@ -0,0 +1,28 @@
+//! Collision layers used to filter physics interactions and spatial queries.
+
+use avian2d::prelude::{LayerMask, PhysicsLayer};
+
+#[derive(PhysicsLayer, Clone, Copy, Debug, Default)]
+pub enum GameLayer {
+    /// Everything without an explicit layer, like platforms and props.
+    #[default]
+    Default,
+    Player,
+    Enemy,
+    Wall,
+    PlayerProjectile,
+    EnemyProjectile,
+}
+
+impl GameLayer {
+    /// Layers that block movement and sight, which excludes projectiles.
+    pub fn solid() -> LayerMask {
+        [
+            GameLayer::Default,
+            GameLayer::Player,
+            GameLayer::Enemy,
+            GameLayer::Wall,
+        ]
+        .into()
+    }
+}
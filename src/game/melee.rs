@@ -0,0 +1,245 @@
+//! Close range attacks of the player. Strikes chain into a combo when the next one is started
+//! within the combo window, and what they look like depends on the [`Age`] of the player: quick
+//! jabs when young, slashes when old and heavy staff swings when ancient. Hits go through the
+//! same [`DamageEvent`]s as everything else.
+
+use avian2d::prelude::{Collider, SpatialQuery, SpatialQueryFilter};
+use bevy::prelude::*;
+
+use crate::{
+    AgedSystems, AppSystems,
+    game::{
+        age::{Age, Aged},
+        health::{DamageEvent, DamageKind, Stunned},
+        layers::GameLayer,
+        player::Player,
+    },
+    screens::Screen,
+};
+
+/// Seconds after a strike in which the next one continues the combo.
+const COMBO_WINDOW: f32 = 0.45;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<Strike>();
+    app.add_systems(
+        Update,
+        (start_strikes, update_strikes)
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(AgedSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Sent when the player wants to strike towards `dir`.
+#[derive(Event)]
+pub struct Strike {
+    pub dir: Vec2,
+}
+
+/// A single strike of a combo.
+#[derive(Clone, Copy, Debug)]
+struct StrikeKind {
+    damage: f32,
+    /// How far in front of the player the hitbox reaches.
+    reach: f32,
+    /// Thickness of the hitbox.
+    width: f32,
+    knockback: f32,
+    stun: f32,
+    /// Seconds before the hitbox comes out.
+    windup: f32,
+    /// Seconds the hitbox stays out.
+    active: f32,
+}
+
+const JABS: [StrikeKind; 3] = [
+    StrikeKind {
+        damage: 10.0,
+        reach: 60.0,
+        width: 24.0,
+        knockback: 80.0,
+        stun: 0.05,
+        windup: 0.02,
+        active: 0.08,
+    },
+    StrikeKind {
+        damage: 10.0,
+        reach: 60.0,
+        width: 24.0,
+        knockback: 80.0,
+        stun: 0.05,
+        windup: 0.02,
+        active: 0.08,
+    },
+    StrikeKind {
+        damage: 18.0,
+        reach: 75.0,
+        width: 30.0,
+        knockback: 220.0,
+        stun: 0.2,
+        windup: 0.05,
+        active: 0.1,
+    },
+];
+
+const SLASHES: [StrikeKind; 2] = [
+    StrikeKind {
+        damage: 18.0,
+        reach: 80.0,
+        width: 50.0,
+        knockback: 150.0,
+        stun: 0.15,
+        windup: 0.1,
+        active: 0.12,
+    },
+    StrikeKind {
+        damage: 26.0,
+        reach: 80.0,
+        width: 60.0,
+        knockback: 260.0,
+        stun: 0.25,
+        windup: 0.12,
+        active: 0.14,
+    },
+];
+
+const STAFF_SWINGS: [StrikeKind; 2] = [
+    StrikeKind {
+        damage: 35.0,
+        reach: 110.0,
+        width: 80.0,
+        knockback: 380.0,
+        stun: 0.4,
+        windup: 0.25,
+        active: 0.15,
+    },
+    StrikeKind {
+        damage: 50.0,
+        reach: 120.0,
+        width: 90.0,
+        knockback: 500.0,
+        stun: 0.6,
+        windup: 0.3,
+        active: 0.18,
+    },
+];
+
+fn combo(age: Age) -> &'static [StrikeKind] {
+    match age {
+        Age::Young => &JABS,
+        Age::Old => &SLASHES,
+        Age::Ancient => &STAFF_SWINGS,
+    }
+}
+
+/// The combo state of the player.
+#[derive(Component, Default)]
+pub struct MeleeCombo {
+    /// How many strikes of the current combo have been done.
+    step: usize,
+    strike: Option<ActiveStrike>,
+    /// Time left to continue the combo.
+    window: f32,
+}
+
+struct ActiveStrike {
+    kind: StrikeKind,
+    dir: Vec2,
+    elapsed: f32,
+    hits: Vec<Entity>,
+    effect: Entity,
+}
+
+fn start_strikes(
+    mut events: EventReader<Strike>,
+    player: Single<(Entity, &mut MeleeCombo, &Aged, Has<Stunned>), With<Player>>,
+    mut commands: Commands,
+) {
+    let (entity, mut combo_state, aged, stunned) = player.into_inner();
+    for event in events.read() {
+        if stunned || combo_state.strike.is_some() {
+            continue;
+        }
+        let strikes = combo(aged.to_age());
+        if combo_state.window <= 0.0 || combo_state.step >= strikes.len() {
+            combo_state.step = 0;
+        }
+        let kind = strikes[combo_state.step];
+        combo_state.step += 1;
+        let dir = event.dir.normalize_or(Vec2::X);
+        let effect = commands
+            .spawn((
+                Name::new("Strike"),
+                Sprite::from_color(
+                    Color::linear_rgba(1.0, 1.0, 1.0, 0.0),
+                    Vec2::new(kind.reach, kind.width),
+                ),
+                Transform::from_translation((dir * kind.reach / 2.0).extend(1.0))
+                    .with_rotation(Quat::from_rotation_z(dir.to_angle())),
+                ChildOf(entity),
+            ))
+            .id();
+        combo_state.strike = Some(ActiveStrike {
+            kind,
+            dir,
+            elapsed: 0.0,
+            hits: Vec::new(),
+            effect,
+        });
+    }
+}
+
+fn update_strikes(
+    player: Single<(Entity, &GlobalTransform, &mut MeleeCombo), With<Player>>,
+    mut effects: Query<&mut Sprite>,
+    spatial_query: SpatialQuery,
+    mut damage: EventWriter<DamageEvent>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let (entity, transform, mut combo_state) = player.into_inner();
+    combo_state.window -= time.delta_secs();
+    let Some(strike) = &mut combo_state.strike else {
+        return;
+    };
+    strike.elapsed += time.delta_secs();
+    let kind = strike.kind;
+    if strike.elapsed >= kind.windup + kind.active {
+        commands.entity(strike.effect).despawn();
+        combo_state.strike = None;
+        combo_state.window = COMBO_WINDOW;
+        return;
+    }
+    if strike.elapsed < kind.windup {
+        return;
+    }
+    if let Ok(mut sprite) = effects.get_mut(strike.effect) {
+        let fade = 1.0 - (strike.elapsed - kind.windup) / kind.active;
+        sprite.color = Color::linear_rgba(1.0, 1.0, 1.0, 0.6 * fade);
+    }
+    let center = transform.translation().xy() + strike.dir * kind.reach / 2.0;
+    let hits = spatial_query.shape_intersections(
+        &Collider::rectangle(kind.reach, kind.width),
+        center,
+        strike.dir.to_angle(),
+        &SpatialQueryFilter::from_mask(GameLayer::Enemy),
+    );
+    for hit in hits {
+        if strike.hits.contains(&hit) {
+            continue;
+        }
+        strike.hits.push(hit);
+        damage.write(DamageEvent {
+            target: hit,
+            source: Some(entity),
+            amount: kind.damage,
+            kind: DamageKind::Physical,
+            knockback: strike.dir * kind.knockback,
+            stun: kind.stun,
+            inflicts_status: true,
+            lethal: false,
+        });
+    }
+}
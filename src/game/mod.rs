@@ -2,13 +2,20 @@ use bevy::prelude::*;
 
 pub(crate) mod age;
 mod animate;
+mod dash;
+pub(crate) mod effects;
 mod enemies;
 mod health;
 mod inputs;
-mod platforms;
+pub(crate) mod items;
+pub(crate) mod layers;
+mod melee;
+pub(crate) mod platforms;
 pub(crate) mod player;
 mod projectile;
+pub(crate) mod spells;
 mod ui;
+mod wall;
 mod world;
 pub mod worldgen;
 mod ysort;
@@ -18,6 +25,9 @@ pub(super) fn plugin(app: &mut App) {
         worldgen::plugin,
         world::plugin,
         inputs::plugin,
+        items::plugin,
+        effects::plugin,
+        spells::plugin,
         player::plugin,
         platforms::plugin,
         animate::plugin,
@@ -28,4 +38,5 @@ pub(super) fn plugin(app: &mut App) {
         projectile::plugin,
         ui::plugin,
     ));
+    app.add_plugins((melee::plugin, dash::plugin, wall::plugin));
 }
@@ -1,12 +1,16 @@
 use avian2d::prelude::*;
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_ecs_ldtk::{prelude::*, utils::ldtk_pixel_coords_to_translation_pivoted};
+use bevy_tnua::TnuaGhostPlatform;
 
 use crate::{
     AgedSystems, AppSystems, PausableSystems, asset_tracking::LoadResource, game::age::Timed,
     screens::Screen,
 };
 
+/// How far into a one-way platform something may sink and still land on it.
+const ONE_WAY_TOLERANCE: f32 = 8.0;
+
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<PlatformAssets>();
     app.add_systems(
@@ -21,9 +25,73 @@ pub(super) fn plugin(app: &mut App) {
             .in_set(AppSystems::Update)
             .run_if(in_state(Screen::WorldGen)),
     );
+    app.add_systems(
+        Update,
+        drop_through
+            .in_set(AppSystems::TickTimers)
+            .run_if(in_state(Screen::Gameplay)),
+    );
     app.register_ldtk_entity_for_layer::<PlatformBundle>("functional", "platform");
 }
 
+/// A platform that can be jumped up through from below and dropped down through.
+#[derive(Clone, Copy, Default, Component)]
+pub struct OneWay;
+
+/// Everything a collider needs to act as a [`OneWay`] platform.
+pub fn one_way() -> impl Bundle {
+    (
+        OneWay,
+        ActiveCollisionHooks::MODIFY_CONTACTS,
+        TnuaGhostPlatform,
+    )
+}
+
+/// Falls through every [`OneWay`] platform until the timer runs out.
+#[derive(Component)]
+pub struct DropThrough(pub Timer);
+
+impl DropThrough {
+    pub fn new(secs: f32) -> Self {
+        Self(Timer::from_seconds(secs, TimerMode::Once))
+    }
+}
+
+/// Lets bodies pass through [`OneWay`] platforms unless they come down onto them from above.
+#[derive(SystemParam)]
+pub struct PlatformHooks<'w, 's> {
+    platforms: Query<'w, 's, &'static ColliderAabb, With<OneWay>>,
+    bodies: Query<'w, 's, (&'static ColliderAabb, Has<DropThrough>), Without<OneWay>>,
+}
+
+impl CollisionHooks for PlatformHooks<'_, '_> {
+    fn modify_contacts(&self, contacts: &mut ContactPair, _commands: &mut Commands) -> bool {
+        let (platform, body) = if self.platforms.contains(contacts.collider1) {
+            (contacts.collider1, contacts.collider2)
+        } else {
+            (contacts.collider2, contacts.collider1)
+        };
+        let (Ok(platform), Ok((body, dropping))) =
+            (self.platforms.get(platform), self.bodies.get(body))
+        else {
+            return true;
+        };
+        !dropping && body.min.y >= platform.max.y - ONE_WAY_TOLERANCE
+    }
+}
+
+fn drop_through(
+    mut query: Query<(Entity, &mut DropThrough)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut drop) in query.iter_mut() {
+        if drop.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<DropThrough>();
+        }
+    }
+}
+
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct PlatformAssets {
@@ -49,6 +117,7 @@ impl FromWorld for PlatformAssets {
 struct Platform {
     width: i32,
     speed: f32,
+    one_way: bool,
 }
 
 impl LdtkEntity for Platform {
@@ -66,7 +135,11 @@ impl LdtkEntity for Platform {
                 .get_float_field("speed")
                 .expect("Platform should have a speed field")
                 .clone(),
-            ..Default::default()
+            one_way: entity_instance
+                .get_bool_field("one_way")
+                .ok()
+                .copied()
+                .unwrap_or(false),
         }
     }
 }
@@ -177,6 +250,9 @@ fn platform_setup(
             .insert(RigidBody::Kinematic)
             .insert(Friction::new(1.0))
             .insert(Timed::default());
+        if platform.one_way {
+            command.insert(one_way());
+        }
 
         let startoffset = -platform.width / 2 + 32;
         for i in 0..width {
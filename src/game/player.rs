@@ -1,5 +1,6 @@
 use avian2d::prelude::{
-    Collider, ColliderAabb, LinearVelocity, LockedAxes, RigidBody, SpatialQuery,
+    Collider, ColliderAabb, CollisionLayers, GravityScale, LayerMask, LinearVelocity, LockedAxes,
+    RigidBody, SpatialQuery,
 };
 use bevy::{
     core_pipeline::{
@@ -11,13 +12,13 @@ use bevy::{
 use bevy_ecs_ldtk::{LdtkEntity, app::LdtkEntityAppExt};
 use bevy_light_2d::light::AmbientLight2d;
 use bevy_tnua::{
-    TnuaAction, TnuaAnimatingState,
+    TnuaAction, TnuaAnimatingState, TnuaGhostSensor,
     builtins::{TnuaBuiltinDash, TnuaBuiltinJumpState},
-    control_helpers::TnuaSimpleAirActionsCounter,
+    control_helpers::{TnuaSimpleAirActionsCounter, TnuaSimpleFallThroughPlatformsHelper},
     prelude::{TnuaBuiltinJump, TnuaBuiltinWalk, TnuaController},
 };
 use bevy_tnua_avian2d::TnuaAvian2dSensorShape;
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{Rng, thread_rng};
 
 use crate::{
     AgedSystems, AppSystems, PausableSystems,
@@ -26,8 +27,16 @@ use crate::{
     game::{
         age::{Age, Aged, Dead, Timed},
         animate::{AnimationConfig, Directional},
+        dash::{DashSettings, Dashing},
+        effects::ItemAcquired,
         enemies::Enemy,
         health::Health,
+        items::{ItemDefs, ItemId},
+        layers::GameLayer,
+        melee::MeleeCombo,
+        spells::SpellBook,
+        wall::{Climber, WallContact},
+        world::footsteps::Footsteps,
         ysort::{ENTITY_LAYER, YSort},
     },
     screens::Screen,
@@ -47,7 +56,7 @@ pub(super) fn plugin(app: &mut App) {
     );
     app.add_systems(
         Update,
-        (drop, pickup, init_item)
+        (drop, pickup, pickup_heal, init_item)
             .in_set(AppSystems::Update)
             .in_set(AgedSystems)
             .run_if(in_state(Screen::Gameplay)),
@@ -73,207 +82,38 @@ pub enum AnimationState {
     Falling,
 }
 
-fn spawn_item(current_items: &Vec<Item>, assets: &PlayerAssets) -> Option<impl Bundle> {
-    let mut items: Vec<_> = [
-        "Immortal Flame",
-        "Book of Fire",
-        "Book of Current",
-        "Duplex",
-        "Solaces Cradle",
-        "Doomsayer",
-    ]
-    .iter()
-    .map(|a| a.to_string())
-    .filter(|a| !current_items.iter().any(|b| b.name == *a))
-    .collect();
-    let mut stats = vec![
-        "Quick Casting".to_string(),
-        "Accelerate Magic".to_string(),
-        "Basics of Magic".to_string(),
-        "Intermediate Magic".to_string(),
-        "Advanced Magic".to_string(),
-    ];
-    items.append(&mut stats);
-    let mut elemental_tomes = vec!["Book of Fire".to_string(), "Book of Current".to_string()];
-    if !current_items
-        .iter()
-        .any(|item| elemental_tomes.contains(&item.name))
-    {
-        items.append(&mut elemental_tomes);
-    }
-    let mut rng = thread_rng();
-    items.shuffle(&mut rng);
-    let size = 32.0;
-    match items.get(0).map(|a| a.as_str()) {
-        Some("Immortal Flame") => {
-            return Some((
-                Name::new("Immortal Flame"),
-                Item {
-                    name: "Immortal Flame".to_string(),
-                    strength: 1.0,
-                    speed: 0.0,
-                },
-                Sprite {
-                    image: assets.sprite_book_red.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Book of Fire") => {
-            return Some((
-                Name::new("Book of Fire"),
-                Item {
-                    name: "Book of Fire".to_string(),
-                    strength: 0.25,
-                    speed: 0.0,
-                },
-                Sprite {
-                    image: assets.sprite_book_red.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Book of Current") => {
-            return Some((
-                Name::new("Book of Current"),
-                Item {
-                    name: "Book of Current".to_string(),
-                    strength: 0.0,
-                    speed: 0.25,
-                },
-                Sprite {
-                    image: assets.sprite_book_blue.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Duplex") => {
-            return Some((
-                Name::new("Duplex"),
-                Item {
-                    name: "Duplex".to_string(),
-                    strength: 0.15,
-                    speed: 0.15,
-                },
-                Sprite {
-                    image: assets.sprite_book_blue.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Cradle of Solace") => {
-            return Some((
-                Name::new("Solaces Cradle"),
-                Item {
-                    name: "Solaces Cradle".to_string(),
-                    strength: 0.0,
-                    speed: 0.0,
-                },
-                Sprite {
-                    image: assets.sprite_book_gold.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Quick Casting") => {
-            return Some((
-                Name::new("Quick Casting"),
-                Item {
-                    name: "Quick Casting".to_string(),
-                    strength: 0.0,
-                    speed: 0.1,
-                },
-                Sprite {
-                    image: assets.sprite_book_gold.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Accelerate Magic") => {
-            return Some((
-                Name::new("Accelerate Magic"),
-                Item {
-                    name: "Accelerate Magic".to_string(),
-                    strength: 0.0,
-                    speed: 0.2,
-                },
-                Sprite {
-                    image: assets.sprite_book_gold.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Basics of Magic") => {
-            return Some((
-                Name::new("Basics of Magic"),
-                Item {
-                    name: "Basics of Magic".to_string(),
-                    strength: 0.1,
-                    speed: 0.0,
-                },
-                Sprite {
-                    image: assets.sprite_book_blue.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Intermediate Magic") => {
-            return Some((
-                Name::new("Intermediate Magic"),
-                Item {
-                    name: "Intermediate Magic".to_string(),
-                    strength: 0.2,
-                    speed: 0.0,
-                },
-                Sprite {
-                    image: assets.sprite_book_blue.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        Some("Advanced Magic") => {
-            return Some((
-                Name::new("Advanced Magic"),
-                Item {
-                    name: "Advanced Magic".to_string(),
-                    strength: 0.3,
-                    speed: 0.0,
-                },
-                Sprite {
-                    image: assets.sprite_book_blue.clone(),
-                    custom_size: Some(Vec2::splat(size)),
-                    ..Default::default()
-                },
-            ));
-        }
-        _ => {
-            return None;
-        }
-    }
+fn spawn_item(item: ItemId, defs: &ItemDefs) -> impl Bundle {
+    let def = defs.get(item);
+    (
+        Name::new(def.name.clone()),
+        Item { id: item },
+        Sprite {
+            image: def.icon.clone(),
+            custom_size: Some(Vec2::splat(32.0)),
+            ..Default::default()
+        },
+    )
 }
 
 #[derive(Clone, Default, Component)]
 pub struct Item {
-    strength: f64,
-    speed: f64,
-    pub(crate) name: String,
+    pub id: ItemId,
 }
 
+/// Restores `amount` health when the player walks over it.
+#[derive(Clone, Default, Component)]
+pub struct HealPickup {
+    pub amount: f32,
+}
+
+/// Chance for a defeated enemy to drop a [`HealPickup`].
+const HEAL_DROP_CHANCE: f64 = 0.3;
+
 #[derive(Clone, Component)]
 pub struct SpellCap {
     pub strength: f64,
     pub speed: f64,
-    pub items: Vec<Item>,
-    pub timer: Timer,
+    pub items: Vec<ItemId>,
 }
 
 impl Default for SpellCap {
@@ -282,25 +122,24 @@ impl Default for SpellCap {
             strength: 1.0,
             speed: 1.0,
             items: Default::default(),
-            timer: Timer::from_seconds(0.0, TimerMode::Once),
         }
     }
 }
 
 impl SpellCap {
-    fn add_item(&mut self, item: Item) {
+    fn add_item(&mut self, item: ItemId, defs: &ItemDefs) {
         self.items.push(item);
         self.strength = self
             .items
             .iter()
-            .map(|a| a.strength)
+            .map(|a| defs.get(*a).strength)
             .reduce(|a, b| a + b)
             .unwrap_or_default()
             + 1.0;
         self.speed = self
             .items
             .iter()
-            .map(|a| a.speed)
+            .map(|a| defs.get(*a).speed)
             .reduce(|a, b| a + b)
             .unwrap_or_default()
             + 1.0;
@@ -314,6 +153,7 @@ pub struct PlayerAssets {
     pub sprite_book_red: Handle<Image>,
     pub sprite_book_gold: Handle<Image>,
     pub sprite_book_blue: Handle<Image>,
+    pub sprite_heal: Handle<Image>,
 
     //Young
     #[dependency]
@@ -398,6 +238,7 @@ impl FromWorld for PlayerAssets {
             sprite_book_red: assets.load("UIElements/Book_1.png"),
             sprite_book_gold: assets.load("UIElements/Book_2.png"),
             sprite_book_blue: assets.load("UIElements/Book_3.png"),
+            sprite_heal: assets.load("UIElements/Tree_Symbol.png"),
 
             aatlas_idle: atlas_idle.clone(),
             aatlas_run: atlas_run.clone(),
@@ -427,11 +268,15 @@ pub struct ItemText(Entity);
 #[derive(Clone, Default, Component)]
 pub struct Book;
 
-fn init_item(items: Query<(Entity, &Item), Added<Item>>, mut commands: Commands) {
+fn init_item(
+    items: Query<(Entity, &Item), Added<Item>>,
+    defs: Res<ItemDefs>,
+    mut commands: Commands,
+) {
     for (entity, item) in items {
         commands.entity(entity).with_child((
             Transform::from_xyz(0.0, 22.0, 100.0),
-            Text2d::new(item.name.clone()),
+            Text2d::new(defs.get(item.id).name.clone()),
             TextFont {
                 font_size: 15.0,
                 ..default()
@@ -449,6 +294,8 @@ fn pickup(
     items: Query<(Entity, &Item, &GlobalTransform, &Children)>,
     spatial_query: SpatialQuery,
     item_texts: Query<(Entity, &ItemText)>,
+    defs: Res<ItemDefs>,
+    mut acquired: EventWriter<ItemAcquired>,
     mut commands: Commands,
 ) {
     let (player, mut inv) = player.into_inner();
@@ -464,7 +311,33 @@ fn pickup(
                 }
             }
             commands.entity(entity).despawn();
-            inv.add_item(item.clone());
+            inv.add_item(item.id, &defs);
+            acquired.write(ItemAcquired(item.id));
+        }
+    }
+}
+
+fn pickup_heal(
+    player: Single<(Entity, &mut Health), With<Player>>,
+    pickups: Query<(Entity, &HealPickup, &GlobalTransform)>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    let (player, mut health) = player.into_inner();
+    for (entity, pickup, transform) in pickups.iter() {
+        // Leave the pickup lying around for later if it would be wasted.
+        if health.health >= health.max_health() {
+            return;
+        }
+        let pos = transform.translation().xy();
+        let size = 40.0;
+        let aabb = ColliderAabb::from_min_max(pos - (size / 2.0), pos + (size / 2.0));
+        if spatial_query
+            .aabb_intersections_with_aabb(aabb)
+            .contains(&player)
+        {
+            health.heal(pickup.amount);
+            commands.entity(entity).despawn();
         }
     }
 }
@@ -473,13 +346,34 @@ fn drop(
     spawn: Query<(&GlobalTransform, Entity), (Added<Dead>, Without<NoDrops>, With<Enemy>)>,
     mut commands: Commands,
     items: Single<&SpellCap>,
+    defs: Res<ItemDefs>,
     playerassets: Res<PlayerAssets>,
 ) {
     let items = items.into_inner();
+    let mut rng = thread_rng();
     for (transform, entity) in spawn.iter() {
         commands.entity(entity).insert(NoDrops);
-        if let Some(item) = spawn_item(&items.items, &playerassets) {
-            commands.spawn(item).insert((
+        if rng.gen_bool(HEAL_DROP_CHANCE) {
+            commands.spawn((
+                Name::new("Heal"),
+                HealPickup { amount: 25.0 },
+                Sprite {
+                    image: playerassets.sprite_heal.clone(),
+                    color: Color::linear_rgb(0.3, 1.0, 0.3),
+                    custom_size: Some(Vec2::splat(24.0)),
+                    ..Default::default()
+                },
+                StateScoped(Screen::Gameplay),
+                Transform::from_translation(transform.translation() + Vec3::X * 20.0),
+                Timed::default(),
+                RigidBody::Dynamic,
+                YSort::new(ENTITY_LAYER, 32.0),
+                Collider::circle(12.0),
+                LockedAxes::ROTATION_LOCKED,
+            ));
+        }
+        if let Some(item) = defs.roll(&items.items, &mut rng) {
+            commands.spawn(spawn_item(item, &defs)).insert((
                 StateScoped(Screen::Gameplay),
                 Transform::from_translation(transform.translation()),
                 Timed::default(),
@@ -523,6 +417,7 @@ fn init_player(
             // engine.
             RigidBody::Dynamic,
             Collider::capsule(16.0, 32.0),
+            CollisionLayers::new(GameLayer::Player, LayerMask::ALL),
             TnuaAnimatingState::<AnimationState>::default(),
             // This is Tnua's interface component.
             TnuaController::default(),
@@ -559,8 +454,19 @@ fn init_player(
             Timed::default(),
             Aged::default(),
             SpellCap::default(),
+            SpellBook::default(),
+            MeleeCombo::default(),
+            DashSettings::default(),
+            Dashing::default(),
+            WallContact::default(),
+            Climber::default(),
+            Footsteps::default(),
+            GravityScale(1.0),
             music(playerassets.music.clone()),
             TnuaSimpleAirActionsCounter::default(),
+            // Lets the player stand on and drop through one-way platforms.
+            TnuaGhostSensor::default(),
+            TnuaSimpleFallThroughPlatformsHelper::default(),
         ))
         .with_child((
             //Book
@@ -1,78 +1,297 @@
 use crate::game::player::Player;
 use crate::game::world::collider::WallCollider;
 use crate::{
-    AgedSystems, AppSystems, PausableSystems,
-    game::{age::Dead, enemies::Enemy, health::Health},
+    AgedSystems, AppSystems, PausableSystems, Turnback,
+    game::{
+        age::Dead,
+        enemies::Enemy,
+        health::{DamageEvent, DamageKind},
+        layers::GameLayer,
+    },
     screens::Screen,
 };
-use avian2d::{
-    parry::query,
-    prelude::{Collider, ColliderAabb, SimpleCollider, SpatialQuery, SpatialQueryFilter},
+use avian2d::prelude::{
+    Collider, CollisionEventsEnabled, CollisionLayers, LayerMask, OnCollisionStart, Sensor,
 };
-use bevy::{ecs::system::command, platform::collections::HashSet, prelude::*};
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+/// How far homing projectiles look for something to chase.
+const HOMING_RANGE: f32 = 400.0;
+/// How many dead projectiles are kept around for reuse.
+const POOL_SIZE: usize = 128;
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ProjectilePool>();
+    app.add_systems(OnExit(Screen::Gameplay), clear_pool);
+    app.add_observer(hit);
     app.add_systems(
         Update,
-        update
+        (init_projectile, update, release_projectiles)
+            .chain()
             .in_set(AgedSystems)
             .in_set(AppSystems::Update)
             .run_if(in_state(Screen::Gameplay)),
     );
 }
-#[derive(PartialEq)]
+#[derive(PartialEq, Default)]
 pub enum ProjectileTarget {
+    #[default]
     Player,
     Enemies,
 }
 
+impl ProjectileTarget {
+    fn layer(&self) -> GameLayer {
+        match self {
+            ProjectileTarget::Player => GameLayer::EnemyProjectile,
+            ProjectileTarget::Enemies => GameLayer::PlayerProjectile,
+        }
+    }
+
+    /// Layers the projectile can hit.
+    fn mask(&self) -> LayerMask {
+        match self {
+            ProjectileTarget::Player => [GameLayer::Player, GameLayer::Wall].into(),
+            ProjectileTarget::Enemies => [GameLayer::Enemy, GameLayer::Wall].into(),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Projectile {
     pub target: ProjectileTarget,
     pub size: Vec2,
     pub dmg: f32,
-    pub dir: Vec2,
+    pub kind: DamageKind,
+    /// The entity that fired the projectile.
+    pub source: Option<Entity>,
+    /// Knockback speed applied to whatever is hit.
+    pub knockback: f32,
+    /// Seconds whatever is hit is staggered for.
+    pub stun: f32,
+    /// Velocity in units per second.
+    pub velocity: Vec2,
+    /// How many more enemies this projectile passes through.
+    pub pierce: u32,
+    pub hits: Vec<Entity>,
+    /// Seconds until the projectile breaks on its own.
+    pub lifetime: f32,
+    /// Distance after which the projectile breaks.
+    pub range: f32,
+    traveled: f32,
+    /// Downward acceleration in units per second squared.
+    pub gravity: f32,
+    /// How fast the projectile turns towards the closest target in radians per second.
+    pub homing: f32,
+}
+
+impl Default for Projectile {
+    fn default() -> Self {
+        Self {
+            target: Default::default(),
+            size: Default::default(),
+            dmg: Default::default(),
+            kind: Default::default(),
+            source: Default::default(),
+            knockback: Default::default(),
+            stun: Default::default(),
+            velocity: Default::default(),
+            pierce: Default::default(),
+            hits: Default::default(),
+            lifetime: 4.0,
+            range: 1500.0,
+            traveled: 0.0,
+            gravity: 0.0,
+            homing: 0.0,
+        }
+    }
+}
+
+/// Dead projectiles that can be handed out again instead of spawning new entities.
+#[derive(Resource, Default)]
+struct ProjectilePool {
+    free: Vec<Entity>,
+}
+
+/// Marks projectiles that return to the [`ProjectilePool`] once they die. Pooled projectiles are not
+/// [`Timed`](crate::game::age::Timed): rewinding leaves them where they are and never brings a dead
+/// one back, so it can be reused right away.
+#[derive(Component)]
+struct Pooled;
+
+/// Spawns projectiles by recycling dead ones from the [`ProjectilePool`] where possible.
+#[derive(SystemParam)]
+pub struct ProjectileSpawner<'w, 's> {
+    pool: ResMut<'w, ProjectilePool>,
+    // A pooled projectile may have been despawned with its screen since it died.
+    dead: Query<'w, 's, (), (With<Pooled>, With<Dead>)>,
+    commands: Commands<'w, 's>,
+}
+
+impl ProjectileSpawner<'_, '_> {
+    /// Spawns a projectile, `bundle` must contain the [`Projectile`] and everything it needs to be
+    /// rendered since a recycled entity keeps the components of its previous life.
+    pub fn spawn(&mut self, bundle: impl Bundle) -> Entity {
+        while let Some(entity) = self.pool.free.pop() {
+            if !self.dead.contains(entity) {
+                continue;
+            }
+            // Removing and reinserting the projectile retriggers `init_projectile`.
+            self.commands
+                .entity(entity)
+                .remove::<Projectile>()
+                .insert(bundle)
+                .remove::<Dead>();
+            return entity;
+        }
+        self.commands.spawn((bundle, Pooled)).id()
+    }
+}
+
+fn release_projectiles(
+    query: Query<Entity, (With<Pooled>, Added<Dead>)>,
+    mut pool: ResMut<ProjectilePool>,
+    mut commands: Commands,
+) {
+    for entity in query.iter() {
+        if pool.free.contains(&entity) {
+            continue;
+        }
+        if pool.free.len() < POOL_SIZE {
+            pool.free.push(entity);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn clear_pool(mut pool: ResMut<ProjectilePool>) {
+    pool.free.clear();
+}
+
+fn init_projectile(query: Query<(Entity, &Projectile), Added<Projectile>>, mut commands: Commands) {
+    for (entity, projectile) in query.iter() {
+        // A sensor so the projectile reports what it touches without being pushed around.
+        commands.entity(entity).insert((
+            Collider::rectangle(projectile.size.x, projectile.size.y),
+            Sensor,
+            CollisionEventsEnabled,
+            CollisionLayers::new(projectile.target.layer(), projectile.target.mask()),
+        ));
+    }
 }
 
 fn update(
-    mut query: Query<(&Projectile, &mut Transform, &GlobalTransform, Entity), Without<Dead>>,
-    mut query_entity: Query<&mut Health>,
+    mut query: Query<(&mut Projectile, &mut Transform, &GlobalTransform, Entity), Without<Dead>>,
 
-    enemies: Query<Entity, With<Enemy>>,
-    walls: Query<Entity, With<WallCollider>>,
-    player: Single<Entity, With<Player>>,
+    enemies: Query<&GlobalTransform, (With<Enemy>, Without<Dead>)>,
+    player: Single<&GlobalTransform, (With<Player>, Without<Projectile>)>,
 
-    spatial_query: SpatialQuery,
+    time: Res<Time>,
     mut command: Commands,
 ) {
-    let player = player.into_inner();
-    for (proj, mut transform, global, entity) in query.iter_mut() {
-        transform.translation.x += proj.dir.x * 0.007;
-        transform.translation.y += proj.dir.y * 0.007;
+    let dt = time.delta_secs();
+    let playerpos = player.translation().xy();
+    for (mut proj, mut transform, global, entity) in query.iter_mut() {
         let pos = global.translation().xy();
-        let aabb = ColliderAabb::from_min_max(pos - (proj.size / 2.0), pos + (proj.size / 2.0));
-        let got_hit = spatial_query.aabb_intersections_with_aabb(aabb);
-        let mut hit = false;
-        for collision in got_hit {
-            if walls.contains(collision) {
-                hit = true;
-                continue;
-            }
-            if collision == player && proj.target == ProjectileTarget::Player {
-                hit = true;
-                if let Ok(mut health) = query_entity.get_mut(collision) {
-                    health.damage(proj.dmg);
-                }
-            }
-            if enemies.contains(collision) && proj.target == ProjectileTarget::Enemies {
-                hit = true;
-                if let Ok(mut health) = query_entity.get_mut(collision) {
-                    health.damage(proj.dmg);
-                }
-            }
-        }
-        if hit {
+        proj.lifetime -= dt;
+        if proj.lifetime <= 0.0 || proj.traveled >= proj.range {
             command.entity(entity).insert(Dead);
+            continue;
         }
+
+        if proj.homing > 0.0 {
+            let target = match proj.target {
+                ProjectileTarget::Player => Some(playerpos),
+                ProjectileTarget::Enemies => enemies
+                    .iter()
+                    .map(|enemy| enemy.translation().xy())
+                    .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos))),
+            };
+            if let Some(target) = target.filter(|target| target.distance(pos) <= HOMING_RANGE) {
+                let angle = proj.velocity.angle_to(target - pos);
+                let max_turn = proj.homing * dt;
+                proj.velocity =
+                    Vec2::from_angle(angle.clamp(-max_turn, max_turn)).rotate(proj.velocity);
+            }
+        }
+        proj.velocity.y -= proj.gravity * dt;
+        let step = proj.velocity * dt;
+        transform.translation += step.extend(0.0);
+        proj.traveled += step.length();
+    }
+}
+
+/// Damages whatever a projectile starts touching. Walls break the projectile, as does any target
+/// it cannot pierce.
+fn hit(
+    trigger: Trigger<OnCollisionStart>,
+    mut projectiles: Query<&mut Projectile, Without<Dead>>,
+    walls: Query<(), With<WallCollider>>,
+    turnback: Res<State<Turnback>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut commands: Commands,
+) {
+    // Whatever rewinds through a projectile must not get hit by it.
+    if turnback.0 {
+        return;
+    }
+    let entity = trigger.target();
+    let Ok(mut proj) = projectiles.get_mut(entity) else {
+        return;
+    };
+    let other = trigger.collider;
+    if walls.contains(other) {
+        commands.entity(entity).insert(Dead);
+        return;
+    }
+    if proj.hits.contains(&other) {
+        return;
+    }
+    proj.hits.push(other);
+    damage.write(DamageEvent {
+        target: other,
+        source: proj.source,
+        amount: proj.dmg,
+        kind: proj.kind,
+        knockback: proj.velocity.normalize_or_zero() * proj.knockback,
+        stun: proj.stun,
+        inflicts_status: true,
+        lethal: false,
+    });
+    if proj.target == ProjectileTarget::Enemies && proj.pierce > 0 {
+        proj.pierce -= 1;
+    } else {
+        commands.entity(entity).insert(Dead);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn spawn(world: &mut World) -> Entity {
+        world
+            .run_system_once(|mut projectiles: ProjectileSpawner| {
+                projectiles.spawn(Projectile::default())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn dead_projectiles_are_reused() {
+        let mut world = World::new();
+        world.init_resource::<ProjectilePool>();
+        let first = spawn(&mut world);
+        world.entity_mut(first).insert(Dead);
+        world.run_system_once(release_projectiles).unwrap();
+
+        let second = spawn(&mut world);
+        assert_eq!(second, first);
+        assert!(!world.entity(second).contains::<Dead>());
+        // The pool is empty again, so the next projectile is a new entity.
+        assert_ne!(spawn(&mut world), first);
     }
 }
@@ -0,0 +1,456 @@
+//! The spells the player can cast.
+//!
+//! Every spell has its own cooldown and costs either mana or time (it ages the player).
+//! The bolt is known from the start, the others are unlocked by picking up elemental books.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    AgedSystems, AppSystems,
+    game::{
+        age::{Aged, Dead},
+        animate::{AnimationConfig, Directional},
+        effects::{Cast, ItemAcquired, owned_effects},
+        enemies::{Enemy, ghost::GhostAssets},
+        health::{DamageEvent, DamageKind, Health, Status},
+        items::{ItemDefs, ItemTag},
+        player::{Player, SpellCap},
+        projectile::{Projectile, ProjectileSpawner, ProjectileTarget},
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<CastSpell>();
+    app.add_systems(
+        Update,
+        (
+            unlock_spells,
+            tick_spell_book,
+            // Bursts before casting, which may hand the dead orb out again as a new projectile.
+            burst_time_stop_orbs,
+            cast_spells,
+            fade_spell_effects,
+        )
+            .chain()
+            .in_set(AppSystems::Update)
+            .in_set(AgedSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Spell {
+    Bolt,
+    FireCone,
+    ChainLightning,
+    TimeStopOrb,
+}
+
+pub enum SpellCost {
+    Mana(f32),
+    /// Ages the player by this amount of [`Aged::time`].
+    Time(f64),
+}
+
+impl Spell {
+    pub const ALL: [Spell; 4] = [
+        Spell::Bolt,
+        Spell::FireCone,
+        Spell::ChainLightning,
+        Spell::TimeStopOrb,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Spell::Bolt => "Bolt",
+            Spell::FireCone => "Fire Cone",
+            Spell::ChainLightning => "Chain Lightning",
+            Spell::TimeStopOrb => "Time Stop Orb",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Spell::Bolt => "UIElements/Sun_Symbol.png",
+            Spell::FireCone => "UIElements/Fire_Symbol.png",
+            Spell::ChainLightning => "UIElements/Diamon_Symbol.png",
+            Spell::TimeStopOrb => "UIElements/Brocken_Clock.png",
+        }
+    }
+
+    /// Cooldown in seconds before casting speed is applied.
+    pub fn cooldown(&self) -> f32 {
+        match self {
+            Spell::Bolt => 1.0,
+            Spell::FireCone => 1.5,
+            Spell::ChainLightning => 2.5,
+            Spell::TimeStopOrb => 8.0,
+        }
+    }
+
+    pub fn cost(&self) -> SpellCost {
+        match self {
+            Spell::Bolt => SpellCost::Mana(5.0),
+            Spell::FireCone => SpellCost::Mana(20.0),
+            Spell::ChainLightning => SpellCost::Mana(30.0),
+            Spell::TimeStopOrb => SpellCost::Time(8.0),
+        }
+    }
+
+    /// Knockback speed and stagger duration of a hit of this spell.
+    pub fn impact(&self) -> (f32, f32) {
+        match self {
+            Spell::Bolt => (120.0, 0.1),
+            Spell::FireCone => (80.0, 0.2),
+            Spell::ChainLightning => (0.0, 0.0),
+            Spell::TimeStopOrb => (0.0, 0.0),
+        }
+    }
+
+    /// The item tag that teaches this spell, `None` if it is known from the start.
+    pub fn unlocked_by(&self) -> Option<ItemTag> {
+        match self {
+            Spell::Bolt => None,
+            Spell::FireCone => Some(ItemTag::Fire),
+            Spell::ChainLightning => Some(ItemTag::Current),
+            Spell::TimeStopOrb => Some(ItemTag::Time),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct SpellBook {
+    /// Known spells in slot order.
+    pub known: Vec<Spell>,
+    selected: usize,
+    pub mana: f32,
+    pub max_mana: f32,
+    /// Mana regenerated per second.
+    pub mana_regen: f32,
+    cooldowns: HashMap<Spell, Timer>,
+}
+
+impl Default for SpellBook {
+    fn default() -> Self {
+        Self {
+            known: vec![Spell::Bolt],
+            selected: 0,
+            mana: 100.0,
+            max_mana: 100.0,
+            mana_regen: 12.0,
+            cooldowns: Default::default(),
+        }
+    }
+}
+
+impl SpellBook {
+    pub fn selected(&self) -> Spell {
+        self.known[self.selected]
+    }
+
+    pub fn cycle(&mut self) {
+        self.selected = (self.selected + 1) % self.known.len();
+    }
+
+    pub fn knows(&self, spell: Spell) -> bool {
+        self.known.contains(&spell)
+    }
+
+    /// Fraction of the cooldown of `spell` that is still remaining.
+    pub fn cooldown_remaining(&self, spell: Spell) -> f32 {
+        self.cooldowns
+            .get(&spell)
+            .map(|timer| timer.fraction_remaining())
+            .unwrap_or_default()
+    }
+
+    fn learn(&mut self, spell: Spell) {
+        if !self.knows(spell) {
+            self.known.push(spell);
+            self.known
+                .sort_by_key(|known| Spell::ALL.iter().position(|s| s == known));
+            self.selected = self.known.iter().position(|s| *s == spell).unwrap();
+        }
+    }
+
+    fn ready(&self, spell: Spell) -> bool {
+        self.cooldowns
+            .get(&spell)
+            .is_none_or(|timer| timer.finished())
+    }
+}
+
+/// Sent when the player wants to cast the selected spell from `origin` towards `dir`.
+#[derive(Event)]
+pub struct CastSpell {
+    pub origin: Vec3,
+    pub dir: Vec2,
+}
+
+/// Freezes every enemy around it in time once the orb hits something.
+#[derive(Component)]
+struct TimeStopOrb {
+    radius: f32,
+    duration: f32,
+}
+
+/// A purely visual spell sprite that is removed after the timer runs out.
+#[derive(Component)]
+struct SpellEffect(Timer);
+
+fn unlock_spells(
+    mut events: EventReader<ItemAcquired>,
+    mut book: Single<&mut SpellBook, With<Player>>,
+    defs: Res<ItemDefs>,
+) {
+    for ItemAcquired(item) in events.read() {
+        let def = defs.get(*item);
+        for spell in Spell::ALL {
+            if spell.unlocked_by().is_some_and(|tag| def.has_tag(tag)) {
+                book.learn(spell);
+            }
+        }
+    }
+}
+
+fn tick_spell_book(mut book: Single<&mut SpellBook, With<Player>>, time: Res<Time>) {
+    book.mana = (book.mana + book.mana_regen * time.delta_secs()).min(book.max_mana);
+    for timer in book.cooldowns.values_mut() {
+        timer.tick(time.delta());
+    }
+}
+
+fn cast_spells(
+    mut events: EventReader<CastSpell>,
+    player: Single<(Entity, &SpellCap, &mut SpellBook, &mut Aged), With<Player>>,
+    enemies: Query<(Entity, &GlobalTransform), (With<Enemy>, Without<Dead>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    assets: Res<GhostAssets>,
+    items: Res<ItemDefs>,
+    mut projectiles: ProjectileSpawner,
+    mut commands: Commands,
+) {
+    let (player, spells, mut book, mut aged) = player.into_inner();
+    for event in events.read() {
+        let spell = book.selected();
+        if !book.ready(spell) {
+            continue;
+        }
+        match spell.cost() {
+            SpellCost::Mana(cost) if book.mana >= cost => book.mana -= cost,
+            SpellCost::Time(cost) if aged.time > cost => aged.time -= cost,
+            _ => continue,
+        }
+        let cooldown = spell.cooldown() / (1.0 + spells.speed as f32 / 10.0);
+        book.cooldowns
+            .insert(spell, Timer::from_seconds(cooldown, TimerMode::Once));
+
+        let mut cast = Cast::new((40.0 + spells.strength * 20.0) as f32);
+        for effect in owned_effects(spells, &items) {
+            effect.on_cast(&mut cast);
+        }
+        let speed = 150.0 + (spells.speed * 25.0) as f32;
+        let (knockback, stun) = spell.impact();
+        let mut hit = |target: Entity, pos: Vec2, amount: f32, kind: DamageKind| {
+            damage_events.write(DamageEvent {
+                target,
+                source: Some(player),
+                amount,
+                kind,
+                knockback: (pos - event.origin.xy()).normalize_or_zero() * knockback,
+                stun,
+                inflicts_status: true,
+                lethal: false,
+            });
+        };
+        match spell {
+            Spell::Bolt => {
+                for dir in cast.directions(event.dir) {
+                    projectiles.spawn(projectile(
+                        event.origin,
+                        Projectile {
+                            target: ProjectileTarget::Enemies,
+                            size: Vec2::splat(30.0),
+                            dmg: cast.dmg,
+                            kind: DamageKind::Arcane,
+                            source: Some(player),
+                            knockback,
+                            stun,
+                            velocity: dir * speed,
+                            pierce: cast.pierce,
+                            ..Default::default()
+                        },
+                        assets.sprite_proj.clone(),
+                        assets.atlas_proj.clone(),
+                        (0, 8),
+                    ));
+                }
+            }
+            Spell::FireCone => {
+                let range = 170.0;
+                let half_angle = 35f32.to_radians();
+                for (enemy, transform) in enemies.iter() {
+                    let pos = transform.translation().xy();
+                    let offset = pos - event.origin.xy();
+                    if offset.length() <= range && event.dir.angle_to(offset).abs() <= half_angle {
+                        hit(enemy, pos, cast.dmg * 0.6, DamageKind::Fire);
+                    }
+                }
+                for i in 0..5 {
+                    let angle = (i as f32 / 4.0 - 0.5) * 2.0 * half_angle;
+                    let pos =
+                        event.origin.xy() + Vec2::from_angle(angle).rotate(event.dir) * range * 0.6;
+                    commands.spawn(spell_effect(
+                        pos.extend(event.origin.z),
+                        assets.sprite_proj_fire.clone(),
+                        assets.atlas_proj_fire.clone(),
+                        (0, 5),
+                        0.35,
+                    ));
+                }
+            }
+            Spell::ChainLightning => {
+                let mut struck: Vec<Entity> = Vec::new();
+                let mut from = event.origin.xy();
+                let mut damage = cast.dmg * 0.8;
+                for jump in 0..4 + cast.pierce {
+                    let (range, max_angle) = if jump == 0 {
+                        (350.0, 45f32.to_radians())
+                    } else {
+                        (200.0, std::f32::consts::PI)
+                    };
+                    let next = enemies
+                        .iter()
+                        .filter(|(enemy, _)| !struck.contains(enemy))
+                        .map(|(enemy, transform)| (enemy, transform.translation().xy()))
+                        .filter(|(_, pos)| {
+                            let offset = *pos - from;
+                            offset.length() <= range
+                                && (jump > 0 || event.dir.angle_to(offset).abs() <= max_angle)
+                        })
+                        .min_by(|(_, a), (_, b)| a.distance(from).total_cmp(&b.distance(from)));
+                    let Some((enemy, pos)) = next else {
+                        break;
+                    };
+                    hit(enemy, pos, damage, DamageKind::Current);
+                    commands.spawn(spell_effect(
+                        pos.extend(event.origin.z),
+                        assets.sprite_proj_electro.clone(),
+                        assets.atlas_proj_electro.clone(),
+                        (3, 5),
+                        0.25,
+                    ));
+                    struck.push(enemy);
+                    from = pos;
+                    damage *= 0.8;
+                }
+            }
+            Spell::TimeStopOrb => {
+                projectiles.spawn((
+                    projectile(
+                        event.origin,
+                        Projectile {
+                            target: ProjectileTarget::Enemies,
+                            size: Vec2::splat(40.0),
+                            dmg: cast.dmg * 0.25,
+                            kind: DamageKind::Time,
+                            source: Some(player),
+                            knockback,
+                            stun,
+                            velocity: event.dir * speed * 0.5,
+                            homing: 1.5,
+                            ..Default::default()
+                        },
+                        assets.sprite_proj.clone(),
+                        assets.atlas_proj.clone(),
+                        (0, 8),
+                    ),
+                    TimeStopOrb {
+                        radius: 160.0,
+                        duration: 3.0,
+                    },
+                ));
+            }
+        }
+    }
+}
+
+fn projectile(
+    origin: Vec3,
+    projectile: Projectile,
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    (first, last): (usize, usize),
+) -> impl Bundle {
+    let atlas = TextureAtlas { layout, index: 0 };
+    let size = projectile.size * 2.0;
+    (
+        StateScoped(Screen::Gameplay),
+        Transform::from_translation(origin),
+        Directional {
+            flipdir: true,
+            ..Default::default()
+        },
+        projectile,
+        Sprite {
+            image: image.clone(),
+            texture_atlas: Some(atlas.clone()),
+            custom_size: Some(size),
+            ..Default::default()
+        },
+        AnimationConfig::new(first, last, 4, true, true, Some(atlas), image),
+    )
+}
+
+fn spell_effect(
+    pos: Vec3,
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    (first, last): (usize, usize),
+    duration: f32,
+) -> impl Bundle {
+    let atlas = TextureAtlas { layout, index: 0 };
+    (
+        StateScoped(Screen::Gameplay),
+        Transform::from_translation(pos),
+        SpellEffect(Timer::from_seconds(duration, TimerMode::Once)),
+        Sprite {
+            image: image.clone(),
+            texture_atlas: Some(atlas.clone()),
+            custom_size: Some(Vec2::splat(60.0)),
+            ..Default::default()
+        },
+        AnimationConfig::new(first, last, 12, true, true, Some(atlas), image),
+    )
+}
+
+fn burst_time_stop_orbs(
+    orbs: Query<(Entity, &GlobalTransform, &TimeStopOrb), Added<Dead>>,
+    mut enemies: Query<(&GlobalTransform, &mut Health), (With<Enemy>, Without<Dead>)>,
+    mut commands: Commands,
+) {
+    for (entity, orb, stop) in orbs.iter() {
+        // The orb goes back to the projectile pool, a reused one must not burst again.
+        commands.entity(entity).remove::<TimeStopOrb>();
+        let center = orb.translation().xy();
+        for (transform, mut health) in enemies.iter_mut() {
+            if transform.translation().xy().distance(center) <= stop.radius {
+                health.apply_status(Status::Frozen, stop.duration);
+            }
+        }
+    }
+}
+
+fn fade_spell_effects(
+    mut query: Query<(Entity, &mut SpellEffect)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut effect) in query.iter_mut() {
+        effect.0.tick(time.delta());
+        if effect.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
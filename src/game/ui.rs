@@ -12,7 +12,15 @@ use bevy::{
 
 use crate::{
     AppSystems, PausableSystems,
-    game::{age::Aged, health::Health, player::Player},
+    game::{
+        age::{Aged, Dead},
+        effects::ItemAcquired,
+        enemies::boss::ActiveBoss,
+        health::Health,
+        items::ItemDefs,
+        player::Player,
+        spells::{Spell, SpellBook},
+    },
     screens::Screen,
 };
 
@@ -20,12 +28,39 @@ use crate::{
 struct Pointer;
 #[derive(Component)]
 struct FillBar;
+#[derive(Component)]
+struct HealthLabel;
+#[derive(Component)]
+struct SpellSlot(Spell);
+#[derive(Component)]
+struct SpellCooldown(Spell);
+#[derive(Component)]
+struct ManaLabel;
+/// A short notification that despawns once its timer runs out.
+#[derive(Component)]
+struct Toast(Timer);
+#[derive(Component)]
+struct BossBar;
+#[derive(Component)]
+struct BossBarFill;
+#[derive(Component)]
+struct BossName;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Gameplay), setup);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (setup, setup_health_label, setup_spell_bar, setup_boss_bar),
+    );
     app.add_systems(
         Update,
-        (update, update_age)
+        (
+            update,
+            update_age,
+            update_spell_bar,
+            update_boss_bar,
+            spawn_pickup_toast,
+            update_toasts,
+        )
             .in_set(AppSystems::Update)
             .in_set(PausableSystems)
             .run_if(in_state(Screen::Gameplay)),
@@ -138,11 +173,188 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
 }
 
-fn update(mut image: Single<&mut Node, With<FillBar>>, health: Single<&Health, With<Player>>) {
+fn setup_health_label(mut commands: Commands) {
+    commands.spawn((
+        Name::new("HealthLabel"),
+        StateScoped(Screen::Gameplay),
+        HealthLabel,
+        Pickable::IGNORE,
+        Text::default(),
+        TextFont::from_font_size(18.0),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Px(150.0),
+            left: Px(50.0),
+            ..default()
+        },
+    ));
+}
+
+fn setup_spell_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            Name::new("SpellBar"),
+            StateScoped(Screen::Gameplay),
+            Pickable::IGNORE,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Px(20.0),
+                width: Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Px(6.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Name::new("ManaLabel"),
+                ManaLabel,
+                Text::default(),
+                TextFont::from_font_size(18.0),
+            ));
+            parent
+                .spawn((
+                    Name::new("SpellSlots"),
+                    Node {
+                        column_gap: Px(8.0),
+                        ..default()
+                    },
+                ))
+                .with_children(|slots| {
+                    for spell in Spell::ALL {
+                        slots.spawn((
+                            Name::new(spell.name()),
+                            SpellSlot(spell),
+                            Node {
+                                width: Px(64.0),
+                                height: Px(64.0),
+                                border: UiRect::all(Px(3.0)),
+                                align_items: AlignItems::End,
+                                ..default()
+                            },
+                            BorderColor(Color::NONE),
+                            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+                            ImageNode::new(asset_server.load_with_settings(
+                                spell.icon(),
+                                |settings: &mut ImageLoaderSettings| {
+                                    settings.sampler = ImageSampler::nearest();
+                                },
+                            )),
+                            children![(
+                                Name::new("Cooldown"),
+                                SpellCooldown(spell),
+                                Node {
+                                    width: Percent(100.0),
+                                    height: Percent(0.0),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                            )],
+                        ));
+                    }
+                });
+        });
+}
+
+fn setup_boss_bar(mut commands: Commands) {
+    commands.spawn((
+        Name::new("BossBar"),
+        StateScoped(Screen::Gameplay),
+        BossBar,
+        Pickable::IGNORE,
+        Node {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            top: Px(20.0),
+            width: Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Px(4.0),
+            ..default()
+        },
+        children![
+            (
+                Name::new("BossName"),
+                BossName,
+                Text::default(),
+                TextFont::from_font_size(22.0),
+            ),
+            (
+                Name::new("BossBarBackground"),
+                Node {
+                    width: Px(500.0),
+                    height: Px(16.0),
+                    border: UiRect::all(Px(2.0)),
+                    ..default()
+                },
+                BorderColor(Color::BLACK),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                children![(
+                    Name::new("BossBarFill"),
+                    BossBarFill,
+                    Node {
+                        width: Percent(100.0),
+                        height: Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.55, 0.1, 0.7)),
+                )],
+            )
+        ],
+    ));
+}
+
+fn update_boss_bar(
+    mut bar: Single<&mut Node, (With<BossBar>, Without<BossBarFill>)>,
+    mut fill: Single<&mut Node, (With<BossBarFill>, Without<BossBar>)>,
+    mut name: Single<&mut Text, With<BossName>>,
+    bosses: Query<(&Health, &Name), (With<ActiveBoss>, Without<Dead>)>,
+) {
+    let Some((health, boss)) = bosses.iter().next() else {
+        bar.display = Display::None;
+        return;
+    };
+    bar.display = Display::Flex;
+    fill.width = Percent(health.get_percent().clamp(0.0, 1.0) * 100.0);
+    name.0 = boss.to_string();
+}
+
+fn update_spell_bar(
+    mut slots: Query<(&SpellSlot, &mut BorderColor, &mut ImageNode)>,
+    mut cooldowns: Query<(&SpellCooldown, &mut Node)>,
+    mut mana: Single<&mut Text, With<ManaLabel>>,
+    book: Single<&SpellBook, With<Player>>,
+) {
+    let book = book.into_inner();
+    for (slot, mut border, mut image) in slots.iter_mut() {
+        border.0 = if book.selected() == slot.0 {
+            Color::WHITE
+        } else {
+            Color::NONE
+        };
+        image.color = if book.knows(slot.0) {
+            Color::WHITE
+        } else {
+            Color::srgba(1.0, 1.0, 1.0, 0.2)
+        };
+    }
+    for (cooldown, mut node) in cooldowns.iter_mut() {
+        node.height = Percent(book.cooldown_remaining(cooldown.0) * 100.0);
+    }
+    mana.0 = format!("Mana {:.0}/{:.0}", book.mana, book.max_mana);
+}
+
+fn update(
+    mut image: Single<&mut Node, With<FillBar>>,
+    mut label: Single<&mut Text, With<HealthLabel>>,
+    health: Single<&Health, With<Player>>,
+) {
     let health = health.into_inner();
     let mut image = image.into_inner();
-    let percent = health.get_percent();
+    let percent = health.get_percent().clamp(0.0, 1.0);
     image.width = Px(180.0 * percent);
+    label.0 = format!("{:.0} / {:.0}", health.health.max(0.0), health.max_health());
 }
 
 fn update_age(mut image: Single<&mut Transform, With<Pointer>>, aged: Single<&Aged, With<Player>>) {
@@ -151,3 +363,62 @@ fn update_age(mut image: Single<&mut Transform, With<Pointer>>, aged: Single<&Ag
     image.rotation = Quat::IDENTITY;
     image.rotate_z((aged.time as f32 / 50.0) * 3.1415);
 }
+
+fn spawn_pickup_toast(
+    mut events: EventReader<ItemAcquired>,
+    defs: Res<ItemDefs>,
+    mut commands: Commands,
+) {
+    for ItemAcquired(item) in events.read() {
+        let def = defs.get(*item);
+        commands.spawn((
+            Name::new("Pickup Toast"),
+            StateScoped(Screen::Gameplay),
+            Toast(Timer::from_seconds(2.5, TimerMode::Once)),
+            Pickable::IGNORE,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Px(20.0),
+                width: Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                column_gap: Px(10.0),
+                ..default()
+            },
+            children![
+                (
+                    ImageNode::new(def.icon.clone()),
+                    Node {
+                        width: Px(40.0),
+                        height: Px(40.0),
+                        ..default()
+                    },
+                ),
+                (
+                    Text(format!("Picked up {}", def.name)),
+                    TextFont::from_font_size(24.0),
+                ),
+            ],
+        ));
+    }
+}
+
+fn update_toasts(
+    mut toasts: Query<(Entity, &mut Toast, &Children)>,
+    mut text: Query<&mut TextColor>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut toast, children) in toasts.iter_mut() {
+        toast.0.tick(time.delta());
+        if toast.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut color) = text.get_mut(child) {
+                color.0.set_alpha(toast.0.fraction_remaining());
+            }
+        }
+    }
+}
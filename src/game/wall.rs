@@ -0,0 +1,126 @@
+//! Wall slides, wall jumps and ledge grabs of the player. [`sense_walls`] feels for a
+//! [`WallCollider`] on either side of the player and for a ledge just above its hands, the
+//! movement in [`inputs`](super::inputs) acts on it depending on the [`WallAbilities`] of the
+//! current [`Age`].
+
+use avian2d::prelude::{SpatialQuery, SpatialQueryFilter};
+use bevy::prelude::*;
+
+use crate::{
+    AgedSystems, AppSystems,
+    game::{
+        age::Age, layers::GameLayer, platforms::OneWay, player::Player,
+        world::collider::WallCollider,
+    },
+    screens::Screen,
+};
+
+/// How far from the center of the player a wall still counts as touched.
+const WALL_REACH: f32 = 20.0;
+/// Height above the center of the player up to which a ledge can be grabbed.
+const LEDGE_HEIGHT: f32 = 40.0;
+/// Fastest the player falls while sliding down a wall.
+pub const SLIDE_SPEED: f32 = 120.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        sense_walls
+            .in_set(AppSystems::Update)
+            .in_set(AgedSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// What the player can do against walls at an age.
+pub struct WallAbilities {
+    pub slide: bool,
+    pub jump: bool,
+    pub grab: bool,
+}
+
+impl WallAbilities {
+    pub fn of(age: Age) -> Self {
+        match age {
+            Age::Young => Self {
+                slide: true,
+                jump: true,
+                grab: true,
+            },
+            Age::Old => Self {
+                slide: true,
+                jump: false,
+                grab: true,
+            },
+            Age::Ancient => Self {
+                slide: false,
+                jump: false,
+                grab: false,
+            },
+        }
+    }
+}
+
+/// The walls the player touches, updated by [`sense_walls`].
+#[derive(Component, Default)]
+pub struct WallContact {
+    /// Side of the touched wall, -1 for the left and 1 for the right.
+    pub side: Option<f32>,
+    /// Top of the touched wall if it is low enough to grab.
+    pub ledge: Option<Vec2>,
+}
+
+/// The state of the player's wall moves.
+#[derive(Component, Default)]
+pub struct Climber {
+    pub hanging: bool,
+    /// Pushes the player away from the wall for a moment after a wall jump.
+    kick: Timer,
+    kick_dir: f32,
+}
+
+impl Climber {
+    pub fn kick(&mut self, dir: f32, secs: f32) {
+        self.kick = Timer::from_seconds(secs, TimerMode::Once);
+        self.kick_dir = dir;
+    }
+
+    /// The direction the player is pushed in by a kick that is still running.
+    pub fn kick_direction(&self) -> Option<f32> {
+        (!self.kick.finished() && !self.kick.duration().is_zero()).then_some(self.kick_dir)
+    }
+
+    pub fn tick(&mut self, delta: std::time::Duration) {
+        self.kick.tick(delta);
+    }
+}
+
+fn sense_walls(
+    player: Single<(&GlobalTransform, &mut WallContact, Entity), With<Player>>,
+    walls: Query<(), (With<WallCollider>, Without<OneWay>)>,
+    spatial_query: SpatialQuery,
+) {
+    let (transform, mut contact, entity) = player.into_inner();
+    let pos = transform.translation().xy();
+    let filter = SpatialQueryFilter::from_mask(GameLayer::Wall).with_excluded_entities([entity]);
+    contact.side = [-1.0, 1.0].into_iter().find(|side| {
+        spatial_query
+            .cast_ray(
+                pos,
+                Dir2::new(Vec2::X * *side).unwrap(),
+                WALL_REACH,
+                true,
+                &filter,
+            )
+            .is_some_and(|hit| walls.contains(hit.entity))
+    });
+    contact.ledge = contact.side.and_then(|side| {
+        // Look down onto the wall from above the hands, a hit right at the start of the ray
+        // means the wall goes on above the ledge height.
+        let start = pos + Vec2::new(side * (WALL_REACH + 4.0), LEDGE_HEIGHT);
+        spatial_query
+            .cast_ray(start, Dir2::NEG_Y, LEDGE_HEIGHT, true, &filter)
+            .filter(|hit| hit.distance > 1.0 && walls.contains(hit.entity))
+            .map(|hit| start - Vec2::Y * hit.distance)
+    });
+}
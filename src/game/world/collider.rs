@@ -6,18 +6,91 @@ use bevy_ecs_ldtk::prelude::*;
 use avian2d::prelude::*;
 
 use crate::{
-    game::{age::Dead, enemies::Enemy, player::Player, worldgen::LevelAssets},
+    game::{
+        age::Age,
+        enemies::Enemy,
+        health::{DamageEvent, DamageKind},
+        layers::GameLayer,
+        platforms::one_way,
+        player::Player,
+        world::{
+            nav::NavGraph,
+            reactive::{age_bound, crumbling},
+        },
+        worldgen::LevelAssets,
+    },
     screens::Screen,
 };
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
-enum Material {
+/// What a wall tile is made of, kept on the wall colliders so everything touching them can tell.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Component)]
+pub enum Material {
     #[default]
     Stone,
     Wood,
     Spikes,
+    Ice,
+    Crumbling,
+    /// Only there while the player is in this age.
+    AgeBound(Age),
     Other,
 }
+
+/// How a [`Material`] feels to walk on.
+#[derive(Clone, Debug)]
+pub struct Surface {
+    pub friction: Friction,
+    pub restitution: Restitution,
+    /// How fast the player reaches its walking speed on it.
+    pub acceleration: f32,
+    pub step_volume: f32,
+    /// Playback speed of the footsteps, higher sounds brighter.
+    pub step_speed: f32,
+    pub dust: Color,
+    /// Dust particles kicked up per step.
+    pub dust_amount: usize,
+}
+
+impl Material {
+    pub fn surface(self) -> Surface {
+        let stone = Surface {
+            friction: Friction::new(1.0),
+            restitution: Restitution::new(0.0),
+            acceleration: f32::INFINITY,
+            step_volume: 0.6,
+            step_speed: 1.0,
+            dust: Color::srgb(0.6, 0.6, 0.6),
+            dust_amount: 2,
+        };
+        match self {
+            Material::Stone | Material::Spikes | Material::AgeBound(_) | Material::Other => stone,
+            Material::Wood => Surface {
+                restitution: Restitution::new(0.1),
+                step_volume: 0.7,
+                step_speed: 1.2,
+                dust: Color::srgb(0.55, 0.4, 0.25),
+                dust_amount: 1,
+                ..stone
+            },
+            Material::Ice => Surface {
+                friction: Friction::new(0.0).with_combine_rule(CoefficientCombine::Min),
+                acceleration: 600.0,
+                step_volume: 0.5,
+                step_speed: 1.4,
+                dust: Color::srgb(0.8, 0.93, 1.0),
+                ..stone
+            },
+            Material::Crumbling => Surface {
+                friction: Friction::new(0.8),
+                step_speed: 0.8,
+                dust: Color::srgb(0.5, 0.42, 0.33),
+                dust_amount: 5,
+                ..stone
+            },
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Wall {
     mat: Material,
@@ -36,6 +109,11 @@ impl LdtkIntCell for Wall {
                 1 => Material::Stone,
                 2 => Material::Wood,
                 3 => Material::Spikes,
+                4 => Material::Ice,
+                5 => Material::Crumbling,
+                6 => Material::AgeBound(Age::Young),
+                7 => Material::AgeBound(Age::Old),
+                8 => Material::AgeBound(Age::Ancient),
                 _ => Material::Other,
             },
         }
@@ -47,6 +125,179 @@ pub struct WallBundle {
     wall: Wall,
 }
 
+/// Identifier of the int-grid layer the walls are read from.
+const COLLIDER_LAYER: &str = "collider";
+
+/// Marks the colliders [`spawn_wall_collision`] generated, so they can be replaced on a rebuild.
+#[derive(Component)]
+struct LevelCollider;
+
+/// The level each wall tile belongs to, and the levels whose colliders are out of date.
+#[derive(Resource, Default)]
+struct WallLevels {
+    levels: HashMap<Entity, Entity>,
+    dirty: HashSet<Entity>,
+}
+
+/// Represents a wide wall that is 1 tile tall
+/// Used to spawn wall collisions
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
+struct Plate {
+    left: i32,
+    right: i32,
+    half_height: bool,
+    left_half: bool,
+    right_half: bool,
+    mat: Material,
+}
+
+/// A simple rectangle type representing a wall of any size
+#[derive(Clone, PartialEq, Debug)]
+struct Rect {
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+    mat: Material,
+}
+
+/// Marks the levels whose wall tiles were added, changed or removed since the last frame.
+fn track_walls(
+    walls: Query<Entity, Changed<Wall>>,
+    mut removed: RemovedComponents<Wall>,
+    parents: Query<&ChildOf>,
+    levels: Query<(), With<LevelIid>>,
+    mut wall_levels: ResMut<WallLevels>,
+) {
+    for entity in walls.iter() {
+        // An intgrid tile sits below a layer entity, so look for the level further up.
+        let Some(level) = parents
+            .iter_ancestors(entity)
+            .find(|ancestor| levels.contains(*ancestor))
+        else {
+            continue;
+        };
+        wall_levels.levels.insert(entity, level);
+        wall_levels.dirty.insert(level);
+    }
+    for entity in removed.read() {
+        if let Some(level) = wall_levels.levels.remove(&entity) {
+            wall_levels.dirty.insert(level);
+        }
+    }
+}
+
+/// Combines the wall tiles of a `width` by `height` grid into as few rectangles as possible.
+///
+/// 1. combine wall tiles into flat "plates" in each individual row
+/// 2. combine the plates into rectangles across multiple rows wherever possible
+fn merge_walls(walls: &HashMap<GridCoords, Material>, width: i32, height: i32) -> Vec<Rect> {
+    // combine wall tiles into flat "plates" in each individual row
+    let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
+
+    for y in 0..height {
+        let mut row_plates: Vec<Plate> = Vec::new();
+        let mut plate = None;
+        // + 1 to the width so the algorithm "terminates" plates that touch the right edge
+        for x in 0..width + 1 {
+            match (
+                &mut plate,
+                walls.get(&GridCoords { x, y }),
+                walls.contains_key(&GridCoords { x, y: y + 1 }),
+            ) {
+                (None, Some(mat), isnothalf) => {
+                    plate = Some(Plate {
+                        left: x,
+                        right: x,
+                        half_height: !isnothalf,
+                        left_half: mat == &Material::Wood,
+                        right_half: mat == &Material::Wood,
+                        mat: *mat,
+                    });
+                }
+                (
+                    Some(Plate {
+                        left: _,
+                        right,
+                        half_height,
+                        left_half: _,
+                        right_half,
+                        mat: plate_mat,
+                    }),
+                    Some(mat),
+                    isnothalf,
+                ) if isnothalf != *half_height && plate_mat == mat => {
+                    *right = x;
+                    *right_half = mat == &Material::Wood;
+                }
+                (Some(cplate), Some(mat), isnothalf) => {
+                    row_plates.push(cplate.clone());
+                    plate = Some(Plate {
+                        left: x,
+                        right: x,
+                        half_height: !isnothalf,
+                        left_half: mat == &Material::Wood,
+                        right_half: mat == &Material::Wood,
+                        mat: *mat,
+                    });
+                }
+                (Some(cplate), None, _) => {
+                    row_plates.push(cplate.clone());
+                    plate = None;
+                }
+                _ => (),
+            }
+        }
+
+        plate_stack.push(row_plates);
+    }
+
+    // combine "plates" into rectangles across multiple rows
+    let mut rect_builder: HashMap<Plate, Rect> = HashMap::new();
+    let mut prev_row: Vec<Plate> = Vec::new();
+    let mut wall_rects: Vec<Rect> = Vec::new();
+
+    // an extra empty row so the algorithm "finishes" the rects that touch the top edge
+    plate_stack.push(Vec::new());
+
+    for (y, current_row) in plate_stack.into_iter().enumerate() {
+        for prev_plate in &prev_row {
+            if !current_row.contains(prev_plate) {
+                // remove the finished rect so that the same plate in the future starts a new rect
+                if let Some(rect) = rect_builder.remove(prev_plate) {
+                    wall_rects.push(rect);
+                }
+            }
+        }
+        for plate in &current_row {
+            rect_builder
+                .entry(plate.clone())
+                .and_modify(|e| e.top += 1.0)
+                .or_insert(Rect {
+                    bottom: y as f32,
+                    top: if plate.half_height {
+                        y as f32 - 0.5
+                    } else {
+                        y as f32
+                    },
+                    left: if plate.left_half {
+                        plate.left as f32 + 0.5
+                    } else {
+                        plate.left as f32
+                    },
+                    right: if plate.right_half {
+                        plate.right as f32 - 0.5
+                    } else {
+                        plate.right as f32
+                    },
+                    mat: plate.mat,
+                });
+        }
+        prev_row = current_row;
+    }
+    wall_rects
+}
+
 /// Spawns heron collisions for the walls of a level
 ///
 /// You could just insert a ColliderBundle into the WallBundle,
@@ -58,291 +309,299 @@ pub struct WallBundle {
 ///
 /// The algorithm used here is a nice compromise between simplicity, speed,
 /// and a small number of rectangle colliders.
-/// In basic terms, it will:
-/// 1. consider where the walls are
-/// 2. combine wall tiles into flat "plates" in each individual row
-/// 3. combine the plates into rectangles across multiple rows wherever possible
-/// 4. spawn colliders for each rectangle
-pub fn spawn_wall_collision(
+/// In basic terms, it will, for every level [`track_walls`] marked:
+/// 1. despawn the colliders generated for it before
+/// 2. consider where the walls are
+/// 3. merge them into rectangles with [`merge_walls`]
+/// 4. spawn colliders for each rectangle, wood becoming [`OneWay`](crate::game::platforms::OneWay)
+///    platforms and crumbling or age bound tiles reacting to time (see [`reactive`](super::reactive))
+/// 5. build the [`NavGraph`] of the level from the rectangles
+fn spawn_wall_collision(
     mut commands: Commands,
-    wall_query: Query<(&GridCoords, &ChildOf, &Wall), Added<Wall>>,
-    parent_query: Query<&ChildOf, Without<Wall>>,
-    level_query: Query<(Entity, &LevelIid)>,
+    mut wall_levels: ResMut<WallLevels>,
+    wall_query: Query<(&GridCoords, &Wall)>,
+    collider_query: Query<(Entity, &ChildOf), With<LevelCollider>>,
+    level_query: Query<&LevelIid>,
     level_assets: Res<LevelAssets>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
 ) {
-    /// Represents a wide wall that is 1 tile tall
-    /// Used to spawn wall collisions
-    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
-    struct Plate {
-        left: i32,
-        right: i32,
-        half_height: bool,
-        left_half: bool,
-        right_half: bool,
-        spikes: bool,
+    if wall_levels.dirty.is_empty() {
+        return;
     }
+    let ldtk_project = ldtk_project_assets
+        .get(level_assets.worlddata.id())
+        .expect("Project should be loaded if level has spawned");
 
-    /// A simple rectangle type representing a wall of any size
-    struct Rect {
-        left: f32,
-        right: f32,
-        top: f32,
-        bottom: f32,
-        spikes: bool,
-    }
-
-    // Consider where the walls are
-    // storing them as GridCoords in a HashSet for quick, easy lookup
-    //
-    // The key of this map will be the entity of the level the wall belongs to.
-    // This has two consequences in the resulting collision entities:
-    // 1. it forces the walls to be split along level boundaries
-    // 2. it lets us easily add the collision entities as children of the appropriate level entity
-    let mut level_to_wall_locations: HashMap<Entity, HashMap<GridCoords, Material>> =
-        HashMap::new();
-
-    wall_query
-        .iter()
-        .for_each(|(&grid_coords, child_of, wall)| {
-            // An intgrid tile's direct parent will be a layer entity, not the level entity
-            // To get the level entity, you need the tile's grandparent.
-            // This is where parent_query comes in.
-            if let Ok(parent_child_of) = parent_query.get(child_of.parent()) {
-                level_to_wall_locations
-                    .entry(parent_child_of.parent())
-                    .or_default()
-                    .insert(grid_coords, wall.mat);
+    for level_entity in std::mem::take(&mut wall_levels.dirty) {
+        for (collider, child_of) in collider_query.iter() {
+            if child_of.parent() == level_entity {
+                commands.entity(collider).despawn();
             }
-        });
+        }
+        // The level itself is gone when its walls were removed by unloading it.
+        let Ok(level_iid) = level_query.get(level_entity) else {
+            continue;
+        };
 
-    if !wall_query.is_empty() {
-        level_query.iter().for_each(|(level_entity, level_iid)| {
-            if let Some(level_walls) = level_to_wall_locations.get(&level_entity) {
-                let ldtk_project = ldtk_project_assets
-                    .get(level_assets.worlddata.id())
-                    .expect("Project should be loaded if level has spawned");
-
-                let level = ldtk_project
-                    .as_standalone()
-                    .get_loaded_level_by_iid(&level_iid.to_string())
-                    .expect("Spawned level should exist in LDtk project");
-
-                let LayerInstance {
-                    c_wid: width,
-                    c_hei: height,
-                    grid_size,
-                    ..
-                } = level.layer_instances()[0];
-
-                // combine wall tiles into flat "plates" in each individual row
-                let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
-
-                for y in 0..height {
-                    let mut row_plates: Vec<Plate> = Vec::new();
-                    let mut plate = None;
-                    // + 1 to the width so the algorithm "terminates" plates that touch the right edge
-                    for mut x in 0..width + 1 {
-                        match (
-                            &mut plate,
-                            level_walls.get(&GridCoords { x, y }),
-                            level_walls.contains_key(&GridCoords { x, y: y + 1 }),
-                        ) {
-                            (None, Some(mat), isnothalf) => {
-                                plate = Some(Plate {
-                                    left: x,
-                                    right: x,
-                                    half_height: !isnothalf,
-                                    left_half: mat == &Material::Wood,
-                                    right_half: mat == &Material::Wood,
-                                    spikes: mat == &Material::Spikes,
-                                });
-                            }
-                            (
-                                Some(Plate {
-                                    left: _,
-                                    right,
-                                    half_height,
-                                    left_half: _,
-                                    right_half,
-                                    spikes,
-                                }),
-                                Some(mat),
-                                isnothalf,
-                            ) if isnothalf != *half_height
-                                && (*spikes == (mat == &Material::Spikes)) =>
-                            {
-                                *right = x;
-                                *right_half = mat == &Material::Wood;
-                            }
-                            (Some(cplate), Some(mat), isnothalf) => {
-                                row_plates.push(cplate.clone());
-                                plate = Some(Plate {
-                                    left: x,
-                                    right: x,
-                                    half_height: !isnothalf,
-                                    left_half: mat == &Material::Wood,
-                                    right_half: mat == &Material::Wood,
-                                    spikes: mat == &Material::Spikes,
-                                });
-                            }
-                            (Some(cplate), None, _) => {
-                                row_plates.push(cplate.clone());
-                                plate = None;
-                            }
+        let level = ldtk_project
+            .as_standalone()
+            .get_loaded_level_by_iid(&level_iid.to_string())
+            .expect("Spawned level should exist in LDtk project");
 
-                            //Start a Rect
-                            // (None, None, Some(currmat), above) => {
-                            //     plate_start = Some(x);
-                            //     lastmat = Some(currmat);
-                            //     startmat = Some(currmat);
-                            //     half_size = Some(!above);
-                            // }
-                            // (Some(_), Some(true), Some(currmat), false)
-                            // | (Some(_), Some(false), Some(currmat), true) => {
-                            //     lastmat = Some(currmat);
-                            // }
-                            // (Some(s), Some(false), Some(currmat), false) => {
-                            //     row_plates.push(Plate {
-                            //         left: s,
-                            //         right: x - 1,
-                            //         half_height: false,
-                            //         left_half: startmat == Some(&Material::Wood),
-                            //         right_half: startmat == Some(&Material::Wood),
-                            //     });
-                            //     plate_start = Some(x);
-                            //     lastmat = Some(currmat);
-                            //     startmat = Some(currmat);
-                            //     half_size = Some(true);
-                            // }
-                            // (Some(s), Some(true), Some(currmat), true) => {
-                            //     row_plates.push(Plate {
-                            //         left: s,
-                            //         right: x - 1,
-                            //         half_height: true,
-                            //         left_half: startmat == Some(&Material::Wood),
-                            //         right_half: lastmat == Some(&Material::Wood),
-                            //     });
-                            //     plate_start = Some(x);
-                            //     lastmat = Some(currmat);
-                            //     startmat = Some(currmat);
-                            //     half_size = Some(false);
-                            // }
-                            // (Some(s), Some(halfsize), None, _) => {
-                            //     row_plates.push(Plate {
-                            //         left: s,
-                            //         right: x - 1,
-                            //         half_height: halfsize,
-                            //         left_half: startmat == Some(&Material::Wood),
-                            //         right_half: lastmat == Some(&Material::Wood),
-                            //     });
-                            //     plate_start = None;
-                            //     half_size = None;
-                            // }
-                            _ => (),
-                        }
-                    }
+        let Some(LayerInstance {
+            c_wid: width,
+            c_hei: height,
+            grid_size,
+            ..
+        }) = level
+            .layer_instances()
+            .iter()
+            .find(|layer| layer.identifier == COLLIDER_LAYER)
+        else {
+            continue;
+        };
+        let (width, height, grid_size) = (*width, *height, *grid_size);
 
-                    plate_stack.push(row_plates);
-                }
-
-                // combine "plates" into rectangles across multiple rows
-                let mut rect_builder: HashMap<Plate, Rect> = HashMap::new();
-                let mut prev_row: Vec<Plate> = Vec::new();
-                let mut wall_rects: Vec<Rect> = Vec::new();
+        // Consider where the walls are
+        // storing them as GridCoords in a HashMap for quick, easy lookup
+        //
+        // Only the walls of this level are considered, which
+        // 1. forces the walls to be split along level boundaries
+        // 2. lets us easily add the collision entities as children of the level entity
+        let level_walls: HashMap<GridCoords, Material> = wall_levels
+            .levels
+            .iter()
+            .filter(|(_, level)| **level == level_entity)
+            .filter_map(|(wall, _)| wall_query.get(*wall).ok())
+            .map(|(grid_coords, wall)| (*grid_coords, wall.mat))
+            .collect();
+        let wall_rects = merge_walls(&level_walls, width, height);
 
-                // an extra empty row so the algorithm "finishes" the rects that touch the top edge
-                plate_stack.push(Vec::new());
+        let grid = grid_size as f32;
+        let walkable: Vec<bevy::math::Rect> = wall_rects
+            .iter()
+            // Enemies should not plan paths over ground that can vanish under them.
+            .filter(|wall_rect| {
+                !matches!(
+                    wall_rect.mat,
+                    Material::Spikes | Material::Crumbling | Material::AgeBound(_)
+                )
+            })
+            .map(|wall_rect| {
+                bevy::math::Rect::new(
+                    wall_rect.left * grid,
+                    wall_rect.bottom * grid,
+                    (wall_rect.right + 1.0) * grid,
+                    (wall_rect.top + 1.0) * grid,
+                )
+            })
+            .collect();
+        commands.entity(level_entity).insert(NavGraph::build(
+            &walkable,
+            Vec2::new(width as f32, height as f32) * grid,
+        ));
 
-                for (y, current_row) in plate_stack.into_iter().enumerate() {
-                    for prev_plate in &prev_row {
-                        if !current_row.contains(prev_plate) {
-                            // remove the finished rect so that the same plate in the future starts a new rect
-                            if let Some(rect) = rect_builder.remove(prev_plate) {
-                                wall_rects.push(rect);
-                            }
-                        }
+        commands.entity(level_entity).with_children(|level| {
+            // Spawn colliders for every rectangle..
+            // Making the collider a child of the level serves two purposes:
+            // 1. Adjusts the transforms to be relative to the level for free
+            // 2. the colliders will be despawned automatically when levels unload
+            for wall_rect in wall_rects {
+                let surface = wall_rect.mat.surface();
+                let size = Vec2::new(
+                    (wall_rect.right - wall_rect.left + 1.) * grid,
+                    (wall_rect.top - wall_rect.bottom + 1.) * grid,
+                );
+                let mut empty = level.spawn_empty();
+                let a = empty
+                    .insert(Collider::rectangle(size.x, size.y))
+                    .insert(RigidBody::Static)
+                    .insert(surface.friction)
+                    .insert(surface.restitution)
+                    .insert(wall_rect.mat)
+                    .insert(Transform::from_xyz(
+                        (wall_rect.left + wall_rect.right + 1.0) * grid / 2.,
+                        (wall_rect.bottom + wall_rect.top + 1.0) * grid / 2.,
+                        0.,
+                    ))
+                    .insert(GlobalTransform::default())
+                    .insert(CollisionLayers::new(GameLayer::Wall, LayerMask::ALL))
+                    .insert((WallCollider, LevelCollider));
+                match wall_rect.mat {
+                    Material::Wood => {
+                        a.insert(one_way());
                     }
-                    for plate in &current_row {
-                        rect_builder
-                            .entry(plate.clone())
-                            .and_modify(|e| e.top += 1.0)
-                            .or_insert(Rect {
-                                bottom: y as f32,
-                                top: if plate.half_height {
-                                    y as f32 - 0.5
-                                } else {
-                                    y as f32
-                                },
-                                left: if plate.left_half {
-                                    plate.left as f32 + 0.5
-                                } else {
-                                    plate.left as f32
-                                },
-                                right: if plate.right_half {
-                                    plate.right as f32 - 0.5
-                                } else {
-                                    plate.right as f32
-                                },
-                                spikes: plate.spikes,
-                            });
+                    Material::Crumbling => {
+                        a.insert(crumbling(size));
                     }
-                    prev_row = current_row;
-                }
-
-                commands.entity(level_entity).with_children(|level| {
-                    // Spawn colliders for every rectangle..
-                    // Making the collider a child of the level serves two purposes:
-                    // 1. Adjusts the transforms to be relative to the level for free
-                    // 2. the colliders will be despawned automatically when levels unload
-                    for wall_rect in wall_rects {
-                        let mut empty = level.spawn_empty();
-                        let a = empty
-                            .insert(Collider::rectangle(
-                                (wall_rect.right as f32 - wall_rect.left as f32 + 1.)
-                                    * grid_size as f32,
-                                (wall_rect.top as f32 - wall_rect.bottom as f32 + 1.)
-                                    * grid_size as f32,
-                            ))
-                            .insert(RigidBody::Static)
-                            .insert(Friction::new(1.0))
-                            .insert(Transform::from_xyz(
-                                (wall_rect.left + wall_rect.right + 1.0) as f32 * grid_size as f32
-                                    / 2.,
-                                (wall_rect.bottom + wall_rect.top + 1.0) as f32 * grid_size as f32
-                                    / 2.,
-                                0.,
-                            ))
-                            .insert(GlobalTransform::default())
-                            .insert(WallCollider);
-                        if wall_rect.spikes {
-                            a.insert((Spikes, Sensor, CollisionEventsEnabled)).observe(
-                                |trigger: Trigger<OnCollisionStart>,
-                                 player_query: Query<&Player>,
-                                 enemy_query: Query<&Enemy>,
-                                 mut commands: Commands| {
-                                    let other_entity = trigger.collider;
-                                    if player_query.contains(other_entity)
-                                        || enemy_query.contains(other_entity)
-                                    {
-                                        commands.entity(other_entity).insert(Dead);
-                                    }
-                                },
-                            );
-                        }
+                    Material::AgeBound(age) => {
+                        a.insert(age_bound(age, size));
                     }
-                });
+                    _ => (),
+                }
+                if wall_rect.mat == Material::Spikes {
+                    a.insert((Spikes, Sensor, CollisionEventsEnabled)).observe(
+                        |trigger: Trigger<OnCollisionStart>,
+                         player_query: Query<&Player>,
+                         enemy_query: Query<&Enemy>,
+                         mut damage: EventWriter<DamageEvent>| {
+                            let other_entity = trigger.collider;
+                            if player_query.contains(other_entity)
+                                || enemy_query.contains(other_entity)
+                            {
+                                damage.write(DamageEvent {
+                                    target: other_entity,
+                                    source: None,
+                                    amount: 0.0,
+                                    kind: DamageKind::Physical,
+                                    knockback: Vec2::ZERO,
+                                    stun: 0.0,
+                                    inflicts_status: true,
+                                    lethal: true,
+                                });
+                            }
+                        },
+                    );
+                }
             }
         });
     }
 }
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<WallLevels>();
     app.add_systems(
         Update,
-        spawn_wall_collision.run_if(in_state(Screen::WorldGen).or(in_state(Screen::Gameplay))),
+        (track_walls, spawn_wall_collision)
+            .chain()
+            .run_if(in_state(Screen::WorldGen).or(in_state(Screen::Gameplay))),
     );
-    app.register_ldtk_int_cell_for_layer::<WallBundle>("collider", 1);
-    app.register_ldtk_int_cell_for_layer::<WallBundle>("collider", 2);
-    app.register_ldtk_int_cell_for_layer::<WallBundle>("collider", 3);
+    for value in 1..=8 {
+        app.register_ldtk_int_cell_for_layer::<WallBundle>(COLLIDER_LAYER, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads a grid drawn top row first: `#` stone, `=` wood, `^` spikes, `~` ice.
+    fn grid(rows: &[&str]) -> (HashMap<GridCoords, Material>, i32, i32) {
+        let height = rows.len() as i32;
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+        let walls = rows
+            .iter()
+            .rev()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.chars().enumerate().filter_map(move |(x, tile)| {
+                    let mat = match tile {
+                        '#' => Material::Stone,
+                        '=' => Material::Wood,
+                        '^' => Material::Spikes,
+                        '~' => Material::Ice,
+                        _ => return None,
+                    };
+                    Some((GridCoords::new(x as i32, y as i32), mat))
+                })
+            })
+            .collect();
+        (walls, width, height)
+    }
+
+    /// The merged rectangles as `(left, right, bottom, top, material)`, bottom row first.
+    fn merge(rows: &[&str]) -> Vec<(f32, f32, f32, f32, Material)> {
+        let (walls, width, height) = grid(rows);
+        let mut rects: Vec<_> = merge_walls(&walls, width, height)
+            .into_iter()
+            .map(|rect| (rect.left, rect.right, rect.bottom, rect.top, rect.mat))
+            .collect();
+        rects.sort_by(|a, b| (a.2, a.0).partial_cmp(&(b.2, b.0)).unwrap());
+        rects
+    }
+
+    #[test]
+    fn empty_grid_has_no_rects() {
+        assert!(merge(&["...", "..."]).is_empty());
+    }
+
+    #[test]
+    fn exposed_row_is_half_height() {
+        assert_eq!(
+            merge(&["###"]),
+            vec![(0.0, 2.0, 0.0, -0.5, Material::Stone)]
+        );
+    }
+
+    #[test]
+    fn covered_rows_merge_below_a_half_height_top() {
+        assert_eq!(
+            merge(&["###", "###", "###"]),
+            vec![
+                (0.0, 2.0, 0.0, 1.0, Material::Stone),
+                (0.0, 2.0, 2.0, 1.5, Material::Stone),
+            ]
+        );
+    }
+
+    #[test]
+    fn separate_columns_stay_separate() {
+        assert_eq!(
+            merge(&["#.#", "#.#"]),
+            vec![
+                (0.0, 0.0, 0.0, 0.0, Material::Stone),
+                (2.0, 2.0, 0.0, 0.0, Material::Stone),
+                (0.0, 0.0, 1.0, 0.5, Material::Stone),
+                (2.0, 2.0, 1.0, 0.5, Material::Stone),
+            ]
+        );
+    }
+
+    #[test]
+    fn plates_split_where_the_height_changes() {
+        assert_eq!(
+            merge(&[".#.", "###"]),
+            vec![
+                (0.0, 0.0, 0.0, -0.5, Material::Stone),
+                (1.0, 1.0, 0.0, 0.0, Material::Stone),
+                (2.0, 2.0, 0.0, -0.5, Material::Stone),
+                (1.0, 1.0, 1.0, 0.5, Material::Stone),
+            ]
+        );
+    }
+
+    #[test]
+    fn materials_are_not_merged() {
+        assert_eq!(
+            merge(&["##~~^"]),
+            vec![
+                (0.0, 1.0, 0.0, -0.5, Material::Stone),
+                (2.0, 3.0, 0.0, -0.5, Material::Ice),
+                (4.0, 4.0, 0.0, -0.5, Material::Spikes),
+            ]
+        );
+    }
+
+    #[test]
+    fn wood_narrows_at_both_ends() {
+        assert_eq!(
+            merge(&[".==."]),
+            vec![(1.5, 1.5, 0.0, -0.5, Material::Wood)]
+        );
+        assert_eq!(
+            merge(&["#==."]),
+            vec![
+                (0.0, 0.0, 0.0, -0.5, Material::Stone),
+                (1.5, 1.5, 0.0, -0.5, Material::Wood),
+            ]
+        );
+    }
+
+    #[test]
+    fn walls_on_the_right_edge_are_closed() {
+        assert_eq!(
+            merge(&["..##"]),
+            vec![(2.0, 3.0, 0.0, -0.5, Material::Stone)]
+        );
+    }
 }
@@ -0,0 +1,151 @@
+//! Footsteps and dust of the player. Every step and landing picks its sound and dust from the
+//! [`Surface`] of the [`Material`] the player stands on.
+
+use avian2d::prelude::LinearVelocity;
+use bevy::{audio::Volume, prelude::*};
+use bevy_tnua::prelude::{TnuaBuiltinWalk, TnuaController};
+use rand::{Rng, seq::SliceRandom, thread_rng};
+
+use crate::{
+    AgedSystems, AppSystems,
+    asset_tracking::LoadResource,
+    audio::sound_effect,
+    game::{player::Player, world::collider::Material},
+    screens::Screen,
+};
+
+/// Seconds between two footsteps at walking speed.
+const STEP_INTERVAL: f32 = 0.3;
+/// Slowest horizontal speed that still counts as walking.
+const WALK_SPEED: f32 = 40.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<FootstepAssets>();
+    app.add_systems(
+        Update,
+        (footsteps, update_dust)
+            .in_set(AppSystems::Update)
+            .in_set(AgedSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct FootstepAssets {
+    #[dependency]
+    steps: Vec<Handle<AudioSource>>,
+}
+
+impl FromWorld for FootstepAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            steps: vec![
+                assets.load("audio/sound_effects/step1.ogg"),
+                assets.load("audio/sound_effects/step2.ogg"),
+                assets.load("audio/sound_effects/step3.ogg"),
+                assets.load("audio/sound_effects/step4.ogg"),
+            ],
+        }
+    }
+}
+
+/// Keeps track of when the player last stepped.
+#[derive(Component, Default)]
+pub struct Footsteps {
+    since_step: f32,
+    grounded: bool,
+}
+
+/// A puff of dust kicked up by a step.
+#[derive(Component)]
+struct Dust {
+    velocity: Vec2,
+    life: Timer,
+}
+
+fn footsteps(
+    player: Single<
+        (
+            &TnuaController,
+            &LinearVelocity,
+            &GlobalTransform,
+            &mut Footsteps,
+        ),
+        With<Player>,
+    >,
+    materials: Query<&Material>,
+    assets: Res<FootstepAssets>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let (controller, velocity, transform, mut steps) = player.into_inner();
+    let ground = controller
+        .concrete_basis::<TnuaBuiltinWalk>()
+        .and_then(|(_, state)| state.standing_on_entity());
+    let landed = ground.is_some() && !steps.grounded;
+    steps.grounded = ground.is_some();
+    let Some(ground) = ground else {
+        steps.since_step = STEP_INTERVAL;
+        return;
+    };
+    steps.since_step += time.delta_secs();
+    let walking = velocity.x.abs() > WALK_SPEED;
+    if !landed && !(walking && steps.since_step >= STEP_INTERVAL) {
+        return;
+    }
+    steps.since_step = 0.0;
+
+    let surface = materials.get(ground).copied().unwrap_or_default().surface();
+    let mut rng = thread_rng();
+    if let Some(step) = assets.steps.choose(&mut rng) {
+        commands.spawn(sound_effect(step.clone())).insert(
+            PlaybackSettings::DESPAWN
+                .with_volume(Volume::Linear(surface.step_volume))
+                .with_speed(surface.step_speed * rng.gen_range(0.9..1.1)),
+        );
+    }
+    // Landing kicks up dust to both sides, steps only behind the player.
+    let (amount, spread) = match landed {
+        true => (surface.dust_amount * 3, 1.0),
+        false => (surface.dust_amount, 0.3),
+    };
+    let feet = transform.translation().xy() - Vec2::Y * 32.0;
+    for _ in 0..amount {
+        let dir = match landed {
+            true => rng.gen_range(-1.0..1.0f32).signum(),
+            false => -velocity.x.signum(),
+        };
+        commands.spawn((
+            Name::new("Dust"),
+            StateScoped(Screen::Gameplay),
+            Sprite::from_color(surface.dust, Vec2::splat(rng.gen_range(3.0..6.0))),
+            Transform::from_translation(feet.extend(3.0)),
+            Dust {
+                velocity: Vec2::new(
+                    dir * rng.gen_range(20.0..60.0),
+                    rng.gen_range(10.0..40.0) * spread,
+                ),
+                life: Timer::from_seconds(rng.gen_range(0.3..0.6), TimerMode::Once),
+            },
+        ));
+    }
+}
+
+fn update_dust(
+    mut query: Query<(Entity, &mut Dust, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut dust, mut transform, mut sprite) in query.iter_mut() {
+        dust.life.tick(time.delta());
+        if dust.life.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += (dust.velocity * time.delta_secs()).extend(0.0);
+        dust.velocity *= 1.0 - 3.0 * time.delta_secs();
+        sprite.color.set_alpha(dust.life.fraction_remaining());
+    }
+}
@@ -2,10 +2,15 @@ use bevy::prelude::*;
 
 pub mod collider;
 mod cosmetic;
+pub mod footsteps;
+pub mod nav;
+mod reactive;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         collider::plugin,
         cosmetic::plugin,
+        footsteps::plugin,
+        reactive::plugin,
     ));
 }
@@ -0,0 +1,308 @@
+//! Navigation graph for ground enemies. Every level gets a [`NavGraph`] built from the merged wall
+//! rectangles of [`spawn_wall_collision`](super::collider::spawn_wall_collision): the tops of the
+//! walls are the walkable surfaces, connected by jump and drop links.
+
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::prelude::*;
+
+/// Highest a jump link may climb.
+const MAX_JUMP_HEIGHT: f32 = 160.0;
+/// Widest gap a jump link may cross.
+const MAX_JUMP_DISTANCE: f32 = 200.0;
+/// How far from the edge of a surface a drop lands.
+const DROP_OFFSET: f32 = 24.0;
+/// How far above a surface something may be while still counting as standing on it.
+const STANDING_HEIGHT: f32 = 120.0;
+
+/// The top of a wall something can walk on, in level space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Surface {
+    pub left: f32,
+    pub right: f32,
+    pub y: f32,
+}
+
+impl Surface {
+    fn contains_x(&self, x: f32) -> bool {
+        (self.left..=self.right).contains(&x)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkKind {
+    Jump,
+    Drop,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Link {
+    to: usize,
+    kind: LinkKind,
+    /// Where on the source surface the link starts.
+    from: Vec2,
+    /// Where on the target surface the link lands.
+    land: Vec2,
+}
+
+impl Link {
+    /// Height the link climbs, drops need no jump at all.
+    fn height(&self) -> f32 {
+        match self.kind {
+            LinkKind::Jump => (self.land.y - self.from.y).max(0.0),
+            LinkKind::Drop => 0.0,
+        }
+    }
+}
+
+/// One leg of a path found by [`NavGraph::find_path`], in world space.
+#[derive(Clone, Copy, Debug)]
+pub struct NavStep {
+    pub kind: LinkKind,
+    /// Walk here, then jump or drop.
+    pub from: Vec2,
+    /// Where the step ends.
+    pub to: Vec2,
+}
+
+impl NavStep {
+    /// Height of the jump needed to take this step.
+    pub fn height(&self) -> f32 {
+        (self.to.y - self.from.y).max(0.0)
+    }
+}
+
+/// The walkable surfaces of a level and the links between them, in level space.
+#[derive(Component, Clone, Debug, Default)]
+pub struct NavGraph {
+    size: Vec2,
+    surfaces: Vec<Surface>,
+    links: Vec<Vec<Link>>,
+}
+
+impl NavGraph {
+    pub fn build(walls: &[Rect], size: Vec2) -> Self {
+        let surfaces = surfaces(walls);
+        let mut links = vec![Vec::new(); surfaces.len()];
+        for (a, surface) in surfaces.iter().enumerate() {
+            // Walking off either edge lands on the highest surface below it.
+            for (edge, outwards) in [(surface.left, -1.0), (surface.right, 1.0)] {
+                let x = edge + outwards * DROP_OFFSET;
+                let below = surfaces
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, other)| other.y < surface.y && other.contains_x(x))
+                    .max_by(|(_, a), (_, b)| a.y.total_cmp(&b.y));
+                if let Some((b, other)) = below {
+                    let fall = Rect::new(x - 8.0, other.y + 2.0, x + 8.0, surface.y - 2.0);
+                    if is_clear(walls, fall) {
+                        links[a].push(Link {
+                            to: b,
+                            kind: LinkKind::Drop,
+                            from: Vec2::new(edge, surface.y),
+                            land: Vec2::new(x, other.y),
+                        });
+                    }
+                }
+            }
+            for (b, other) in surfaces.iter().enumerate() {
+                if a == b || other.y - surface.y > MAX_JUMP_HEIGHT {
+                    continue;
+                }
+                let Some((from, land)) = jump_points(surface, other) else {
+                    continue;
+                };
+                if from.distance(land) > MAX_JUMP_DISTANCE + MAX_JUMP_HEIGHT {
+                    continue;
+                }
+                let arc = Rect::new(
+                    from.x.min(land.x),
+                    from.y.min(land.y) + 2.0,
+                    from.x.max(land.x),
+                    from.y.max(land.y) + 48.0,
+                );
+                if is_clear(walls, arc) {
+                    links[a].push(Link {
+                        to: b,
+                        kind: LinkKind::Jump,
+                        from,
+                        land,
+                    });
+                }
+            }
+        }
+        Self {
+            size,
+            surfaces,
+            links,
+        }
+    }
+
+    /// Whether `pos` in level space lies inside the level.
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.cmpge(Vec2::ZERO).all() && pos.cmple(self.size).all()
+    }
+
+    /// The surface something at `pos` stands on.
+    fn surface_below(&self, pos: Vec2) -> Option<usize> {
+        self.surfaces
+            .iter()
+            .enumerate()
+            .filter(|(_, surface)| {
+                surface.contains_x(pos.x)
+                    && surface.y <= pos.y + 8.0
+                    && pos.y - surface.y <= STANDING_HEIGHT
+            })
+            .max_by(|(_, a), (_, b)| a.y.total_cmp(&b.y))
+            .map(|(index, _)| index)
+    }
+
+    /// Finds the jumps and drops leading from `start` to `goal` with A*, skipping jumps higher
+    /// than `max_jump`. `origin` is the world position of the level. Returns an empty path if both
+    /// are already on the same surface and `None` if there is no way there.
+    pub fn find_path(
+        &self,
+        origin: Vec2,
+        start: Vec2,
+        goal: Vec2,
+        max_jump: f32,
+    ) -> Option<Vec<NavStep>> {
+        let start = start - origin;
+        let goal = goal - origin;
+        let first = self.surface_below(start)?;
+        let last = self.surface_below(goal)?;
+        if first == last {
+            return Some(Vec::new());
+        }
+
+        let mut cost = vec![f32::INFINITY; self.surfaces.len()];
+        let mut entry = vec![start; self.surfaces.len()];
+        let mut came_from: Vec<Option<(usize, Link)>> = vec![None; self.surfaces.len()];
+        let mut open = BinaryHeap::new();
+        cost[first] = 0.0;
+        open.push(Candidate {
+            estimate: start.distance(goal),
+            surface: first,
+        });
+        while let Some(Candidate { surface, .. }) = open.pop() {
+            if surface == last {
+                break;
+            }
+            for link in &self.links[surface] {
+                if link.height() > max_jump {
+                    continue;
+                }
+                let next = cost[surface]
+                    + (link.from.x - entry[surface].x).abs()
+                    + link.from.distance(link.land);
+                if next < cost[link.to] {
+                    cost[link.to] = next;
+                    entry[link.to] = link.land;
+                    came_from[link.to] = Some((surface, *link));
+                    open.push(Candidate {
+                        estimate: next + link.land.distance(goal),
+                        surface: link.to,
+                    });
+                }
+            }
+        }
+
+        let mut path = Vec::new();
+        let mut current = last;
+        while current != first {
+            let (previous, link) = came_from[current]?;
+            path.push(NavStep {
+                kind: link.kind,
+                from: link.from + origin,
+                to: link.land + origin,
+            });
+            current = previous;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+#[derive(PartialEq)]
+struct Candidate {
+    estimate: f32,
+    surface: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the `BinaryHeap` pops the cheapest candidate first.
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The tops of the walls that are not covered by another wall, merged where they touch.
+fn surfaces(walls: &[Rect]) -> Vec<Surface> {
+    let mut surfaces: Vec<Surface> = Vec::new();
+    for wall in walls {
+        let mut open = vec![(wall.min.x, wall.max.x)];
+        for other in walls {
+            if other.min.y > wall.max.y + 1.0 || other.max.y <= wall.max.y + 1.0 {
+                continue;
+            }
+            open = open
+                .into_iter()
+                .flat_map(|(left, right)| {
+                    [
+                        (left, right.min(other.min.x)),
+                        (left.max(other.max.x), right),
+                    ]
+                })
+                .filter(|(left, right)| right - left > 1.0)
+                .collect();
+        }
+        surfaces.extend(open.into_iter().map(|(left, right)| Surface {
+            left,
+            right,
+            y: wall.max.y,
+        }));
+    }
+    surfaces.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.left.total_cmp(&b.left)));
+    let mut merged: Vec<Surface> = Vec::new();
+    for surface in surfaces {
+        match merged.last_mut() {
+            Some(last) if last.y == surface.y && surface.left - last.right <= 1.0 => {
+                last.right = last.right.max(surface.right);
+            }
+            _ => merged.push(surface),
+        }
+    }
+    merged
+}
+
+/// Where to take off from `from` and where to land on `to`, if `to` is close enough to jump to.
+fn jump_points(from: &Surface, to: &Surface) -> Option<(Vec2, Vec2)> {
+    let (takeoff, land) = if to.left >= from.right {
+        (from.right - 8.0, to.left + DROP_OFFSET)
+    } else if to.right <= from.left {
+        (from.left + 8.0, to.right - DROP_OFFSET)
+    } else if to.y > from.y && from.left < to.left {
+        // Overlapping surfaces above can only be reached by jumping around their edge.
+        (to.left - DROP_OFFSET, to.left + DROP_OFFSET)
+    } else if to.y > from.y && from.right > to.right {
+        (to.right + DROP_OFFSET, to.right - DROP_OFFSET)
+    } else {
+        return None;
+    };
+    if (takeoff - land).abs() > MAX_JUMP_DISTANCE || !from.contains_x(takeoff) {
+        return None;
+    }
+    Some((Vec2::new(takeoff, from.y), Vec2::new(land, to.y)))
+}
+
+fn is_clear(walls: &[Rect], area: Rect) -> bool {
+    walls.iter().all(|wall| wall.intersect(area).is_empty())
+}
@@ -0,0 +1,152 @@
+//! Wall tiles that react to time. Crumbling tiles fall apart shortly after the player stands on
+//! them and, being [`Timed`], come back together when time is rewound. Age bound tiles only exist
+//! while the player is in their [`Age`], so rewinding into another age brings them back as well.
+
+use avian2d::prelude::{Collider, ColliderDisabled, SpatialQuery, SpatialQueryFilter};
+use bevy::prelude::*;
+use bevy_tnua::prelude::{TnuaBuiltinWalk, TnuaController};
+
+use crate::{
+    AgedSystems, AppSystems,
+    game::{
+        age::{Age, Aged, Dead, Timed},
+        layers::GameLayer,
+        player::Player,
+        ysort::BACKGROUND_LAYER,
+    },
+    screens::Screen,
+};
+
+/// Seconds a crumbling tile holds the player before falling apart.
+const CRUMBLE_TIME: f32 = 0.6;
+const CRUMBLING_COLOR: Color = Color::srgb(0.54, 0.42, 0.31);
+const CRACKED_COLOR: Color = Color::srgb(0.3, 0.22, 0.16);
+/// Opacity of age bound tiles while the player is in another age.
+const GHOST_ALPHA: f32 = 0.15;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (crumble, age_tiles)
+            .in_set(AppSystems::Update)
+            .in_set(AgedSystems)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// A wall that falls apart shortly after the player stood on it.
+#[derive(Component, Default)]
+pub struct Crumbling {
+    timer: Option<Timer>,
+}
+
+/// A wall that only exists while the player is in this age.
+#[derive(Component)]
+pub struct AgeBound(pub Age);
+
+fn age_color(age: Age) -> Color {
+    match age {
+        Age::Young => Color::srgb(0.48, 0.88, 0.48),
+        Age::Old => Color::srgb(0.88, 0.76, 0.48),
+        Age::Ancient => Color::srgb(0.65, 0.48, 0.88),
+    }
+}
+
+/// Makes a wall collider of `size` crumble.
+pub fn crumbling(size: Vec2) -> impl Bundle {
+    (
+        Crumbling::default(),
+        Timed::default(),
+        children![(
+            Sprite::from_color(CRUMBLING_COLOR, size),
+            Transform::from_xyz(0.0, 0.0, BACKGROUND_LAYER as f32),
+        )],
+    )
+}
+
+/// Makes a wall collider of `size` exist only in `age`.
+pub fn age_bound(age: Age, size: Vec2) -> impl Bundle {
+    (
+        AgeBound(age),
+        children![(
+            Sprite::from_color(age_color(age), size),
+            Transform::from_xyz(0.0, 0.0, BACKGROUND_LAYER as f32),
+        )],
+    )
+}
+
+fn crumble(
+    player: Single<&TnuaController, With<Player>>,
+    mut tiles: Query<(Entity, &mut Crumbling, &Children), Without<Dead>>,
+    mut sprites: Query<&mut Sprite>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let standing_on = player
+        .concrete_basis::<TnuaBuiltinWalk>()
+        .and_then(|(_, state)| state.standing_on_entity());
+    for (entity, mut crumbling, children) in tiles.iter_mut() {
+        if standing_on == Some(entity) && crumbling.timer.is_none() {
+            crumbling.timer = Some(Timer::from_seconds(CRUMBLE_TIME, TimerMode::Once));
+        }
+        let Some(timer) = &mut crumbling.timer else {
+            continue;
+        };
+        timer.tick(time.delta());
+        // Darken while cracking and look whole again once gone, for when it is rewound.
+        let color = match timer.finished() {
+            true => CRUMBLING_COLOR,
+            false => CRUMBLING_COLOR.mix(&CRACKED_COLOR, timer.fraction()),
+        };
+        let mut iter = sprites.iter_many_mut(children);
+        while let Some(mut sprite) = iter.fetch_next() {
+            sprite.color = color;
+        }
+        if timer.finished() {
+            crumbling.timer = None;
+            commands.entity(entity).insert(Dead);
+        }
+    }
+}
+
+fn age_tiles(
+    aged: Single<&Aged, With<Player>>,
+    tiles: Query<(
+        Entity,
+        &AgeBound,
+        &Collider,
+        &GlobalTransform,
+        &Children,
+        Has<ColliderDisabled>,
+    )>,
+    spatial_query: SpatialQuery,
+    mut sprites: Query<&mut Sprite>,
+    mut commands: Commands,
+) {
+    let age = aged.to_age();
+    let filter = SpatialQueryFilter::from_mask([GameLayer::Player, GameLayer::Enemy]);
+    for (entity, bound, collider, transform, children, disabled) in tiles.iter() {
+        let present = bound.0 == age;
+        if present != disabled {
+            continue;
+        }
+        // A tile coming back around someone would trap them inside, so it waits until they left.
+        if present
+            && !spatial_query
+                .shape_intersections(collider, transform.translation().xy(), 0.0, &filter)
+                .is_empty()
+        {
+            continue;
+        }
+        match present {
+            true => commands.entity(entity).remove::<ColliderDisabled>(),
+            false => commands.entity(entity).insert(ColliderDisabled),
+        };
+        // Tiles of other ages stay faintly visible so the player can plan around them.
+        let alpha = if present { 1.0 } else { GHOST_ALPHA };
+        let mut iter = sprites.iter_many_mut(children);
+        while let Some(mut sprite) = iter.fetch_next() {
+            sprite.color.set_alpha(alpha);
+        }
+    }
+}
@@ -41,6 +41,10 @@ struct Room {
     bb: Aabb2d,
 }
 
+/// The difficulty of a placed room, read from the level's `difficulty` field in LDtk.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct RoomDifficulty(pub i32);
+
 #[derive(Resource, Default)]
 pub struct WorldGen {
     rooms: Vec<RoomRef>,
@@ -102,6 +106,7 @@ impl RoomRef {
             Room {
                 bb: self.bb.translated_by(translation),
             },
+            RoomDifficulty(self.difficulty),
         )
     }
 }
@@ -55,7 +55,7 @@ impl Plugin for AppPlugin {
         );
         app.add_plugins((
             LdtkPlugin,
-            PhysicsPlugins::default(),
+            PhysicsPlugins::default().with_collision_hooks::<game::platforms::PlatformHooks>(),
             EnhancedInputPlugin,
             TnuaControllerPlugin::new(FixedUpdate),
             TnuaAvian2dPlugin::new(FixedUpdate),
@@ -0,0 +1,114 @@
+//! The inventory menu, listing the items the player has collected.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    game::{
+        items::{ItemDefs, ItemId},
+        player::{Player, SpellCap},
+    },
+    menus::Menu,
+    theme::{palette::LABEL_TEXT, widget},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::Inventory), spawn_inventory_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(
+            in_state(Menu::Inventory)
+                .and(input_just_pressed(KeyCode::Escape).or(input_just_pressed(KeyCode::KeyI))),
+        ),
+    );
+}
+
+fn spawn_inventory_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    spells: Single<&SpellCap, With<Player>>,
+    defs: Res<ItemDefs>,
+) {
+    let spells = spells.into_inner();
+    let items: Vec<_> = spells
+        .items
+        .iter()
+        .map(|item| item_row(*item, &defs))
+        .collect();
+    commands
+        .spawn((
+            widget::ui_root("Inventory Menu"),
+            GlobalZIndex(2),
+            StateScoped(Menu::Inventory),
+        ))
+        .with_children(|parent| {
+            parent.spawn(widget::header("Inventory"));
+            parent.spawn(widget::label(format!(
+                "Strength {:.2}   Speed {:.2}",
+                spells.strength, spells.speed
+            )));
+            parent
+                .spawn((
+                    Name::new("Item List"),
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Px(6.0),
+                        max_height: Percent(60.0),
+                        overflow: Overflow::scroll_y(),
+                        ..default()
+                    },
+                ))
+                .with_children(|list| {
+                    if items.is_empty() {
+                        list.spawn(widget::label("No items collected yet."));
+                    }
+                    for item in items {
+                        list.spawn(item);
+                    }
+                });
+            parent.spawn(widget::button("Back", go_back_on_click, &asset_server));
+        });
+}
+
+fn item_row(item: ItemId, defs: &ItemDefs) -> impl Bundle + use<> {
+    let def = defs.get(item);
+    (
+        Name::new(def.name.clone()),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(12.0),
+            ..default()
+        },
+        children![
+            (
+                ImageNode::new(def.icon.clone()),
+                Node {
+                    width: Px(48.0),
+                    height: Px(48.0),
+                    ..default()
+                },
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                children![
+                    widget::label(def.name.clone()),
+                    (
+                        Text(def.description.clone()),
+                        TextFont::from_font_size(16.0),
+                        TextColor(LABEL_TEXT),
+                    ),
+                ],
+            ),
+        ],
+    )
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::None);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::None);
+}